@@ -0,0 +1,389 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One reviewer's ranking of other models, best first. Ballots are usually
+/// partial: a reviewer never ranks its own answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ballot {
+    pub reviewer: String,
+    pub ranked: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub position: usize,
+    pub model: String,
+    /// Number of ballots that ranked this model
+    pub appearances: usize,
+    pub first_place_votes: usize,
+    /// Sum over ballots of (ballot length - 1 - index)
+    pub borda: usize,
+    /// Borda points divided by the maximum this model could have scored on
+    /// the ballots it appeared in, so models skipped by more reviewers are
+    /// not penalized
+    pub borda_normalized: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_rank: Option<f64>,
+    /// Pairwise wins minus pairwise losses
+    pub copeland: i64,
+    /// Number of models this one beats through Schulze strongest paths
+    pub schulze_wins: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tally {
+    pub ballots: usize,
+    pub candidates: Vec<String>,
+    pub leaderboard: Vec<LeaderboardEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condorcet_winner: Option<String>,
+    /// pairwise[a][b] = number of ballots ranking a above b
+    pub pairwise: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl Tally {
+    /// Plain-text leaderboard for prompts and human-readable output.
+    pub fn summary(&self) -> String {
+        let mut lines: Vec<String> = self
+            .leaderboard
+            .iter()
+            .map(|e| {
+                let mean_rank = e
+                    .mean_rank
+                    .map(|r| format!("{:.2}", r))
+                    .unwrap_or_else(|| "n/a".to_string());
+                format!(
+                    "{}. {} - Borda {} ({:.0}%), mean rank {}, Copeland {:+}, first-place votes {}/{}",
+                    e.position,
+                    e.model,
+                    e.borda,
+                    e.borda_normalized * 100.0,
+                    mean_rank,
+                    e.copeland,
+                    e.first_place_votes,
+                    e.appearances
+                )
+            })
+            .collect();
+        lines.push(match &self.condorcet_winner {
+            Some(winner) => format!("Condorcet winner: {}", winner),
+            None => "Condorcet winner: none (pairwise cycle or tie)".to_string(),
+        });
+        lines.join("\n")
+    }
+}
+
+/// Turn Stage2 results (as loaded by `finalize::load_stage2_reviews`) into
+/// ballots. Labels that could not be resolved to a model are dropped.
+pub fn ballots_from_reviews(stage2_results: &[Value]) -> Vec<Ballot> {
+    stage2_results
+        .iter()
+        .filter_map(|review| {
+            let ranking = review.get("ranking")?;
+            let reviewer = ranking["reviewer"].as_str()?.to_string();
+            let ranked: Vec<String> = ranking["ranking"]
+                .as_array()?
+                .iter()
+                .filter_map(|entry| entry["model"].as_str().map(|m| m.to_string()))
+                .collect();
+            if ranked.is_empty() {
+                None
+            } else {
+                Some(Ballot { reviewer, ranked })
+            }
+        })
+        .collect()
+}
+
+/// Combine ballots with Borda count, mean rank, Copeland and Schulze.
+///
+/// `candidates` lists every model that answered; models that appear on
+/// ballots but not in `candidates` are added. Pairs a ballot does not rank
+/// are treated as abstentions rather than losses.
+pub fn tally(candidates: &[String], ballots: &[Ballot]) -> Tally {
+    let mut names: Vec<String> = candidates.to_vec();
+    for ballot in ballots {
+        for model in &ballot.ranked {
+            if !names.contains(model) {
+                names.push(model.clone());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    let n = names.len();
+    let index = |model: &str| names.iter().position(|m| m == model);
+
+    let mut appearances = vec![0usize; n];
+    let mut first_place = vec![0usize; n];
+    let mut borda = vec![0usize; n];
+    let mut borda_max = vec![0usize; n];
+    let mut rank_sum = vec![0usize; n];
+    let mut d = vec![vec![0usize; n]; n];
+
+    for ballot in ballots {
+        // Ignore duplicate entries on a ballot, keeping the first position
+        let mut seen: Vec<usize> = Vec::new();
+        for model in &ballot.ranked {
+            if let Some(i) = index(model) {
+                if !seen.contains(&i) {
+                    seen.push(i);
+                }
+            }
+        }
+        let len = seen.len();
+        for (pos, &i) in seen.iter().enumerate() {
+            appearances[i] += 1;
+            if pos == 0 {
+                first_place[i] += 1;
+            }
+            borda[i] += len - 1 - pos;
+            borda_max[i] += len - 1;
+            rank_sum[i] += pos + 1;
+            for &j in &seen[pos + 1..] {
+                d[i][j] += 1;
+            }
+        }
+    }
+
+    // Copeland and Condorcet from the pairwise matrix
+    let mut copeland = vec![0i64; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && d[i][j] > d[j][i] {
+                copeland[i] += 1;
+                copeland[j] -= 1;
+            }
+        }
+    }
+    let condorcet_winner = (0..n)
+        .find(|&i| n > 1 && (0..n).all(|j| i == j || d[i][j] > d[j][i]))
+        .map(|i| names[i].clone());
+
+    // Schulze strongest paths (widest path over winning margins)
+    let mut p = vec![vec![0usize; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && d[i][j] > d[j][i] {
+                p[i][j] = d[i][j];
+            }
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            if i == k {
+                continue;
+            }
+            for j in 0..n {
+                if j == i || j == k {
+                    continue;
+                }
+                p[i][j] = p[i][j].max(p[i][k].min(p[k][j]));
+            }
+        }
+    }
+    let schulze_wins: Vec<usize> = (0..n)
+        .map(|i| (0..n).filter(|&j| i != j && p[i][j] > p[j][i]).count())
+        .collect();
+
+    let normalized = |i: usize| {
+        if borda_max[i] == 0 {
+            0.0
+        } else {
+            borda[i] as f64 / borda_max[i] as f64
+        }
+    };
+    let mean_rank = |i: usize| {
+        if appearances[i] == 0 {
+            None
+        } else {
+            Some(rank_sum[i] as f64 / appearances[i] as f64)
+        }
+    };
+
+    // Order by Schulze, then normalized Borda, then mean rank, then name
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| {
+        schulze_wins[b]
+            .cmp(&schulze_wins[a])
+            .then(normalized(b).total_cmp(&normalized(a)))
+            .then(
+                mean_rank(a)
+                    .unwrap_or(f64::MAX)
+                    .total_cmp(&mean_rank(b).unwrap_or(f64::MAX)),
+            )
+            .then(names[a].cmp(&names[b]))
+    });
+
+    let leaderboard = order
+        .iter()
+        .enumerate()
+        .map(|(pos, &i)| LeaderboardEntry {
+            position: pos + 1,
+            model: names[i].clone(),
+            appearances: appearances[i],
+            first_place_votes: first_place[i],
+            borda: borda[i],
+            borda_normalized: normalized(i),
+            mean_rank: mean_rank(i),
+            copeland: copeland[i],
+            schulze_wins: schulze_wins[i],
+        })
+        .collect();
+
+    let pairwise = (0..n)
+        .map(|i| {
+            let row = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| (names[j].clone(), d[i][j]))
+                .collect();
+            (names[i].clone(), row)
+        })
+        .collect();
+
+    Tally {
+        ballots: ballots.len(),
+        candidates: names,
+        leaderboard,
+        condorcet_winner,
+        pairwise,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ballot(reviewer: &str, ranked: &[&str]) -> Ballot {
+        Ballot {
+            reviewer: reviewer.to_string(),
+            ranked: ranked.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    fn names(models: &[&str]) -> Vec<String> {
+        models.iter().map(|m| m.to_string()).collect()
+    }
+
+    fn entry<'a>(tally: &'a Tally, model: &str) -> &'a LeaderboardEntry {
+        tally.leaderboard.iter().find(|e| e.model == model).unwrap()
+    }
+
+    fn order(tally: &Tally) -> Vec<&str> {
+        tally.leaderboard.iter().map(|e| e.model.as_str()).collect()
+    }
+
+    /// 3 x a>b>c, 2 x b>c>a, 2 x c>a>b: a beats b 5-2, b beats c 5-2 and
+    /// c beats a 4-3, so there is no Condorcet winner and every model has
+    /// one pairwise win and one loss.
+    fn cycle() -> Vec<Ballot> {
+        let mut ballots = Vec::new();
+        for i in 0..3 {
+            ballots.push(ballot(&format!("r{}", i), &["a", "b", "c"]));
+        }
+        for i in 3..5 {
+            ballots.push(ballot(&format!("r{}", i), &["b", "c", "a"]));
+        }
+        for i in 5..7 {
+            ballots.push(ballot(&format!("r{}", i), &["c", "a", "b"]));
+        }
+        ballots
+    }
+
+    #[test]
+    fn schulze_breaks_a_condorcet_cycle_by_strongest_paths() {
+        let tally = tally(&names(&["a", "b", "c"]), &cycle());
+        assert_eq!(tally.condorcet_winner, None);
+        assert_eq!(tally.pairwise["a"]["b"], 5);
+        assert_eq!(tally.pairwise["b"]["c"], 5);
+        assert_eq!(tally.pairwise["c"]["a"], 4);
+
+        // a→b 5 and a→b→c 5 beat c→a 4 and b→c→a 4; b→c 5 beats c→a→b 4
+        assert_eq!(order(&tally), ["a", "b", "c"]);
+        let wins: Vec<usize> = tally.leaderboard.iter().map(|e| e.schulze_wins).collect();
+        assert_eq!(wins, [2, 1, 0]);
+    }
+
+    #[test]
+    fn borda_and_mean_rank_count_positions() {
+        let tally = tally(&names(&["a", "b", "c"]), &cycle());
+        // Points 2/1/0 per ballot: a = 3*2 + 2*0 + 2*1
+        assert_eq!(entry(&tally, "a").borda, 8);
+        assert_eq!(entry(&tally, "b").borda, 7);
+        assert_eq!(entry(&tally, "c").borda, 6);
+        assert!((entry(&tally, "a").borda_normalized - 8.0 / 14.0).abs() < 1e-9);
+        // a = (3*1 + 2*3 + 2*2) / 7
+        assert!((entry(&tally, "a").mean_rank.unwrap() - 13.0 / 7.0).abs() < 1e-9);
+        assert_eq!(entry(&tally, "b").mean_rank, Some(2.0));
+        assert_eq!(entry(&tally, "a").first_place_votes, 3);
+        assert_eq!(entry(&tally, "c").appearances, 7);
+    }
+
+    #[test]
+    fn copeland_ties_in_a_cycle_and_on_even_pairs() {
+        let tally_cycle = tally(&names(&["a", "b", "c"]), &cycle());
+        for model in ["a", "b", "c"] {
+            assert_eq!(entry(&tally_cycle, model).copeland, 0);
+        }
+
+        // A 1-1 pair is neither a win nor a loss; everything else ties too,
+        // so the name decides
+        let even = tally(&names(&["b", "a"]), &[ballot("x", &["a", "b"]), ballot("y", &["b", "a"])]);
+        assert_eq!(entry(&even, "a").copeland, 0);
+        assert_eq!(entry(&even, "b").copeland, 0);
+        assert_eq!(even.condorcet_winner, None);
+        assert_eq!(order(&even), ["a", "b"]);
+    }
+
+    #[test]
+    fn partial_ballots_abstain_on_unranked_pairs() {
+        // Each reviewer skips its own answer
+        let ballots = [
+            ballot("a", &["b", "c"]),
+            ballot("b", &["a", "c"]),
+            ballot("c", &["a", "b"]),
+        ];
+        let tally = tally(&names(&["a", "b", "c"]), &ballots);
+        // Only c's ballot compares a and b
+        assert_eq!(tally.pairwise["a"]["b"], 1);
+        assert_eq!(tally.pairwise["b"]["a"], 0);
+        assert_eq!(tally.condorcet_winner.as_deref(), Some("a"));
+        assert_eq!(order(&tally), ["a", "b", "c"]);
+
+        let a = entry(&tally, "a");
+        assert_eq!((a.appearances, a.borda, a.copeland), (2, 2, 2));
+        assert_eq!(a.borda_normalized, 1.0);
+        assert_eq!(a.mean_rank, Some(1.0));
+        let b = entry(&tally, "b");
+        assert_eq!((b.borda, b.copeland), (1, 0));
+        assert_eq!(b.borda_normalized, 0.5);
+        assert_eq!(b.mean_rank, Some(1.5));
+        assert_eq!(entry(&tally, "c").copeland, -2);
+    }
+
+    #[test]
+    fn duplicate_labels_keep_their_first_position() {
+        let tally = tally(&names(&["a", "b", "c"]), &[ballot("r", &["a", "b", "a", "c"])]);
+        let a = entry(&tally, "a");
+        assert_eq!((a.appearances, a.borda, a.mean_rank), (1, 2, Some(1.0)));
+        assert_eq!(entry(&tally, "b").borda, 1);
+        assert_eq!(entry(&tally, "c").mean_rank, Some(3.0));
+        assert_eq!(tally.pairwise["b"]["a"], 0);
+    }
+
+    #[test]
+    fn unranked_candidates_stay_on_the_leaderboard() {
+        // z answered but nobody ranked it; x was ranked without answering
+        let tally = tally(&names(&["a", "b", "z"]), &[ballot("r", &["a", "x", "b"])]);
+        assert_eq!(tally.candidates, ["a", "b", "x", "z"]);
+        assert_eq!(order(&tally), ["a", "x", "b", "z"]);
+
+        let z = entry(&tally, "z");
+        assert_eq!((z.appearances, z.borda, z.copeland, z.schulze_wins), (0, 0, 0, 0));
+        assert_eq!(z.borda_normalized, 0.0);
+        assert_eq!(z.mean_rank, None);
+        assert_eq!(tally.condorcet_winner, None);
+        assert!(tally.summary().contains("z - Borda 0 (0%), mean rank n/a"));
+    }
+}
@@ -1,72 +1,580 @@
+use crate::config::expand_home;
+use crate::engines::{EngineConfig, EngineRegistry, PromptVia, TokenUsage};
+use crate::openai_runner::run_openai;
 use anyhow::{Context, Result};
+use std::env;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::io::AsyncWriteExt;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::process::Command;
 
-// Currently unused but kept for future compatibility with external CLI tools
-#[allow(dead_code)]
-pub async fn run_llm(engine: &str, prompt: &str) -> Result<String> {
-    let bin = match engine {
-        "claude" => "claude",
-        "gemini" => "gemini-cli",
-        "cursor-agent" => "cursor-agent",
-        "codex" => "codex-cli",
-        _ => {
-            return Err(anyhow::anyhow!(
-                "Unknown engine: {}. Use one of: 'claude', 'gemini', 'cursor-agent', 'codex'",
-                engine
-            ));
+/// Stderr (or an HTTP error body) kept for error messages: the last this
+/// many bytes.
+pub const STDERR_TAIL_BYTES: usize = 4096;
+
+/// Why an engine produced no answer. Returned inside `anyhow::Error`, so
+/// callers can `downcast_ref` to tell a hung model from a missing binary.
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("CLI tool '{command}' not found in PATH. Please install it first.")]
+    NotFound { command: String },
+    /// `command` is the endpoint URL for HTTP engines
+    #[error("'{command}' did not answer within {timeout_secs}s and was stopped")]
+    Timeout { command: String, timeout_secs: u64 },
+    #[error("CLI tool '{command}' failed with {}: {stderr_tail}", exit_description(*.code))]
+    NonZeroExit {
+        command: String,
+        /// `None` when the process was killed by a signal
+        code: Option<i32>,
+        stderr_tail: String,
+    },
+    /// CLI output past the cap is dropped instead, since raw text survives
+    /// truncation; a cut-off JSON body does not
+    #[error("{url} sent more than {limit} bytes; raise max_output_bytes to accept it")]
+    ResponseTooLarge { url: String, limit: usize },
+    #[error("{url} returned HTTP {status}: {body_tail}")]
+    HttpStatus {
+        url: String,
+        status: u16,
+        body_tail: String,
+    },
+}
+
+fn exit_description(code: Option<i32>) -> String {
+    match code {
+        Some(code) => format!("exit code {}", code),
+        None => "no exit code (killed by a signal)".to_string(),
+    }
+}
+
+/// An answer and the engine that produced it.
+#[derive(Debug, Clone)]
+pub struct EngineAnswer {
+    pub text: String,
+    /// The engine that answered: the one asked for, or one of its fallbacks
+    pub engine: String,
+    /// Calls made to `engine`
+    pub attempts: u32,
+    /// Reported by HTTP engines only
+    pub usage: Option<TokenUsage>,
+}
+
+/// Ask the engine registered as `engine` (built in or from the config file)
+/// to answer `prompt`, retrying as its `retry` policy allows and then trying
+/// each engine in its `fallback` list the same way.
+///
+/// When every engine fails, the last engine's error is returned (so an
+/// `EngineError` can still be downcast), with the earlier ones as context.
+pub async fn run_llm(registry: &EngineRegistry, engine: &str, prompt: &str) -> Result<EngineAnswer> {
+    let primary = registry.get(engine)?;
+    let mut failures = Vec::new();
+    let mut last_error = None;
+    for name in std::iter::once(engine).chain(primary.fallback.iter().map(String::as_str)) {
+        if let Some((failed, e)) = last_error.take() {
+            log_warn!("{} failed; falling back to {}", failed, name);
+            failures.push(format!("{}: {:#}", failed, e));
         }
-    };
+        let result = match registry.get(name) {
+            Ok(config) => run_with_retries(name, config, prompt).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(answer) => return Ok(answer),
+            Err(e) => last_error = Some((name, e)),
+        }
+    }
+    let (failed, last_error) = last_error.expect("at least the requested engine was tried");
+    if failures.is_empty() {
+        return Err(last_error);
+    }
+    Err(last_error.context(format!(
+        "Fallback {} failed too; earlier failures:\n  {}",
+        failed,
+        failures.join("\n  ")
+    )))
+}
 
-    // Check if binary exists
-    let which_output = Command::new("which")
-        .arg(bin)
-        .output()
-        .await
-        .context("Failed to check if CLI tool exists")?;
-
-    if !which_output.status.success() {
-        return Err(anyhow::anyhow!(
-            "CLI tool '{}' not found in PATH. Please install it first.",
-            bin
-        ));
+async fn run_with_retries(name: &str, engine: &EngineConfig, prompt: &str) -> Result<EngineAnswer> {
+    let policy = &engine.retry;
+    let mut attempt = 1;
+    loop {
+        let result = match &engine.openai {
+            Some(api) => run_openai(api, engine, prompt).await,
+            None => run_engine(engine, prompt).await.map(|text| (text, None)),
+        };
+        match result {
+            Ok((text, usage)) => {
+                return Ok(EngineAnswer {
+                    text,
+                    engine: name.to_string(),
+                    attempts: attempt,
+                    usage,
+                })
+            }
+            Err(e) if attempt < policy.max_attempts && is_retryable(engine, &e) => {
+                let wait = policy.backoff(attempt + 1);
+                log_warn!(
+                    "{} attempt {}/{} failed, retrying in {}ms: {:#}",
+                    name,
+                    attempt,
+                    policy.max_attempts,
+                    wait.as_millis(),
+                    e
+                );
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn is_retryable(engine: &EngineConfig, error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<EngineError>() {
+        Some(EngineError::NotFound { .. }) => false,
+        Some(EngineError::Timeout { .. }) => engine.retry.retry_on_timeout,
+        Some(EngineError::NonZeroExit {
+            code, stderr_tail, ..
+        }) => engine.retry.retries_exit(*code, stderr_tail),
+        Some(EngineError::HttpStatus {
+            status, body_tail, ..
+        }) => engine.retry.retries_status(*status, body_tail),
+        // The same request gets the same answer
+        Some(EngineError::ResponseTooLarge { .. }) => false,
+        // Spawn failures and unusable output would fail the same way again
+        None => false,
+    }
+}
+
+/// Find `command` the way a shell would: as given when it contains a path
+/// separator, else in each `PATH` directory.
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let path = expand_home(command);
+    if command.contains(std::path::MAIN_SEPARATOR) || command.contains('/') {
+        return path.is_file().then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(command))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+/// Run one engine on `prompt` and return its answer.
+///
+/// The prompt is written while stdout and stderr are read, so a child that
+/// prints before it has read everything cannot deadlock on a full pipe.
+/// The engine runs in its own process group, which is killed when
+/// `timeout_secs` passes or when this future is dropped.
+pub async fn run_engine(engine: &EngineConfig, prompt: &str) -> Result<String> {
+    if engine.command.is_empty() {
+        anyhow::bail!("Engine has no command; set `command`, or `openai` for an HTTP engine");
     }
+    let bin = find_executable(&engine.command).ok_or_else(|| EngineError::NotFound {
+        command: engine.command.clone(),
+    })?;
 
-    let mut child = Command::new(bin)
-        .stdin(Stdio::piped())
+    let mut command = Command::new(&bin);
+    command
+        .args(engine.command_args(prompt))
+        .envs(&engine.env)
+        .stdin(if engine.prompt_via == PromptVia::Stdin {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+    if let Some(dir) = &engine.working_dir {
+        command.current_dir(expand_home(dir));
+    }
+    log_debug!(
+        "Running {} ({} prompt chars via {:?}, timeout {}s)",
+        bin.display(),
+        prompt.len(),
+        engine.prompt_via,
+        engine.timeout_secs
+    );
+
+    let mut child = command
         .spawn()
-        .context(format!("Failed to spawn {}", bin))?;
-
-    {
-        let mut stdin = child
-            .stdin
-            .take()
-            .context("Failed to take stdin")?;
-        stdin
-            .write_all(prompt.as_bytes())
-            .await
-            .context("Failed to write to stdin")?;
-        stdin.flush().await.context("Failed to flush stdin")?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .await
-        .context("Failed to wait for CLI process")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!(
-            "CLI tool '{}' failed with exit code {}: {}",
-            bin,
-            output.status.code().unwrap_or(-1),
-            stderr
+        .context(format!("Failed to spawn {}", bin.display()))?;
+    let mut group = ProcessGroup(child.id());
+
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take().context("CLI stdout was not captured")?;
+    let stderr = child.stderr.take().context("CLI stderr was not captured")?;
+    let write_prompt = async {
+        if let Some(mut stdin) = stdin {
+            match stdin.write_all(prompt.as_bytes()).await {
+                // The engine is free to exit without reading everything
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => {}
+                other => other.context("Failed to write to stdin")?,
+            }
+            // Dropping stdin closes it so the engine sees end of input
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    let run = async {
+        let (written, stdout, stderr, status) = tokio::join!(
+            write_prompt,
+            read_head(stdout, engine.max_output_bytes),
+            read_tail(stderr, STDERR_TAIL_BYTES),
+            child.wait()
+        );
+        written?;
+        let (stdout, truncated) = stdout.context("Failed to read CLI stdout")?;
+        let stderr = stderr.context("Failed to read CLI stderr")?;
+        let status = status.context("Failed to wait for CLI process")?;
+        Ok::<_, anyhow::Error>((stdout, truncated, stderr, status))
+    };
+
+    let timeout = Duration::from_secs(engine.timeout_secs);
+    let (stdout, truncated, stderr, status) = match tokio::time::timeout(timeout, run).await {
+        Ok(finished) => finished?,
+        Err(_) => {
+            group.kill();
+            let _ = child.wait().await;
+            return Err(EngineError::Timeout {
+                command: engine.command.clone(),
+                timeout_secs: engine.timeout_secs,
+            }
+            .into());
+        }
+    };
+    // The leader has been reaped, so its pid may be reused from here on
+    group.disarm();
+
+    if !status.success() {
+        return Err(EngineError::NonZeroExit {
+            command: engine.command.clone(),
+            code: status.code(),
+            stderr_tail: String::from_utf8_lossy(&stderr).trim().to_string(),
+        }
+        .into());
+    }
+    if truncated {
+        log_warn!(
+            "{} printed more than {} bytes; the rest was dropped",
+            engine.command,
+            engine.max_output_bytes
+        );
+    }
+
+    engine.extract(&String::from_utf8_lossy(&stdout))
+}
+
+/// Read everything, keeping the first `limit` bytes. Returns whether
+/// anything was dropped.
+async fn read_head(mut reader: impl AsyncRead + Unpin, limit: usize) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok((kept, truncated));
+        }
+        let room = limit.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+        truncated |= n > room;
+    }
+}
+
+/// Read everything, keeping the last `limit` bytes.
+async fn read_tail(mut reader: impl AsyncRead + Unpin, limit: usize) -> std::io::Result<Vec<u8>> {
+    let mut kept = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(kept);
+        }
+        kept.extend_from_slice(&buf[..n]);
+        if kept.len() > limit {
+            kept.drain(..kept.len() - limit);
+        }
+    }
+}
+
+/// The process group an engine runs in. Killed on drop, so a cancelled
+/// run does not leave the engine or its children behind.
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    fn disarm(&mut self) {
+        self.0 = None;
+    }
+
+    #[cfg(unix)]
+    fn kill(&mut self) {
+        if let Some(pid) = self.0.take() {
+            // The group id is the leader's pid; a negative pid signals the group
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+
+    /// Without process groups only the child itself is killed, by
+    /// `kill_on_drop`.
+    #[cfg(not(unix))]
+    fn kill(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::engines::RetryPolicy;
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::time::Instant;
+
+    /// A scratch directory for one test's scripts and counter files.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mcp-council-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn registry(engines: Value) -> EngineRegistry {
+        let config: Config = serde_json::from_value(json!({ "engines": engines })).unwrap();
+        EngineRegistry::from_config(&config)
+    }
+
+    /// An `sh -c` engine that appends `name` to `log` on every call, then
+    /// runs `then`.
+    fn sh(log: &Path, name: &str, then: &str) -> Value {
+        json!({
+            "command": "sh",
+            "args": ["-c", format!("cat >/dev/null; echo {} >> '{}'; {}", name, log.display(), then)]
+        })
+    }
+
+    fn calls(log: &Path) -> Vec<String> {
+        fs::read_to_string(log)
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn engine(config: Value) -> EngineConfig {
+        serde_json::from_value(config).unwrap()
+    }
+
+    /// Whether `pid` has exited; a killed orphan may linger as a zombie
+    /// until init reaps it.
+    #[cfg(unix)]
+    fn exited(pid: &str) -> bool {
+        match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => stat.rsplit_once(") ").is_some_and(|(_, rest)| rest.starts_with('Z')),
+            Err(_) => true,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeouts_kill_the_whole_process_group() {
+        let dir = scratch("timeout");
+        let pid_file = dir.join("grandchild.pid");
+        let slow = engine(json!({
+            "command": "sh",
+            "args": ["-c", format!("sleep 30 & echo $! > '{}'; wait", pid_file.display())],
+            "timeout_secs": 1
+        }));
+
+        let started = Instant::now();
+        let error = run_engine(&slow, "q").await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<EngineError>(),
+            Some(EngineError::Timeout { timeout_secs: 1, .. })
         ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !exited(pid) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(exited(pid), "grandchild {} outlived the timeout", pid);
+    }
+
+    #[tokio::test]
+    async fn missing_commands_are_not_found() {
+        let missing = engine(json!({ "command": "mcp-council-no-such-cli" }));
+        let error = run_engine(&missing, "q").await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<EngineError>(),
+            Some(EngineError::NotFound { command }) if command == "mcp-council-no-such-cli"
+        ));
+    }
+
+    #[tokio::test]
+    async fn non_zero_exits_carry_the_end_of_stderr() {
+        let failing = engine(json!({
+            "command": "sh",
+            "args": ["-c", "cat >/dev/null; head -c 10000 /dev/zero | tr '\\0' x >&2; echo ' the end' >&2; exit 3"]
+        }));
+        let error = run_engine(&failing, "q").await.unwrap_err();
+        match error.downcast_ref::<EngineError>() {
+            Some(EngineError::NonZeroExit {
+                code, stderr_tail, ..
+            }) => {
+                assert_eq!(*code, Some(3));
+                assert!(stderr_tail.ends_with("x the end"), "{:?}", stderr_tail);
+                assert!(stderr_tail.len() <= STDERR_TAIL_BYTES);
+            }
+            other => panic!("expected NonZeroExit, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn stdout_is_capped_at_max_output_bytes() {
+        let chatty = engine(json!({
+            "command": "sh",
+            "args": ["-c", "cat >/dev/null; head -c 1000000 /dev/zero | tr '\\0' y"],
+            "max_output_bytes": 1000
+        }));
+        let answer = run_engine(&chatty, "q").await.unwrap();
+        assert_eq!(answer, "y".repeat(1000));
+    }
+
+    #[tokio::test]
+    async fn large_prompts_do_not_deadlock_on_full_pipes() {
+        // Echoes the prompt back while it is still being written, so both
+        // pipes fill unless stdin and stdout are handled together
+        let echo = engine(json!({ "command": "cat", "timeout_secs": 20 }));
+        let prompt = "0123456789abcdef".repeat(256 * 1024);
+        let answer = run_engine(&echo, &prompt).await.unwrap();
+        assert_eq!(answer.len(), prompt.len());
+    }
+
+    #[test]
+    fn backoff_doubles_from_the_first_retry() {
+        let policy = RetryPolicy {
+            backoff_ms: 100,
+            ..RetryPolicy::default()
+        };
+        let waits: Vec<u128> = (2..=5).map(|attempt| policy.backoff(attempt).as_millis()).collect();
+        assert_eq!(waits, [100, 200, 400, 800]);
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.to_string())
+    #[tokio::test]
+    async fn retryable_exits_are_retried_with_backoff() {
+        let dir = scratch("retry");
+        let log = dir.join("calls");
+        // Fails with 75 twice, then answers
+        let mut engine = sh(
+            &log,
+            "flaky",
+            &format!(
+                "[ $(wc -l < '{}') -ge 3 ] && echo done || {{ echo busy >&2; exit 75; }}",
+                log.display()
+            ),
+        );
+        engine["retry"] = json!({ "max_attempts": 4, "backoff_ms": 50, "retry_exit_codes": [75] });
+        let registry = registry(json!({ "flaky": engine }));
+
+        let started = Instant::now();
+        let answer = run_llm(&registry, "flaky", "q").await.unwrap();
+        assert_eq!(answer.text, "done");
+        assert_eq!(answer.attempts, 3);
+        assert_eq!(calls(&log).len(), 3);
+        // 50ms before the second attempt, 100ms before the third
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn attempts_stop_at_max_attempts() {
+        let dir = scratch("max-attempts");
+        let log = dir.join("calls");
+        let mut engine = sh(&log, "down", "exit 75");
+        engine["retry"] = json!({ "max_attempts": 3, "backoff_ms": 1, "retry_exit_codes": [75] });
+        let registry = registry(json!({ "down": engine }));
+
+        let error = run_llm(&registry, "down", "q").await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<EngineError>(),
+            Some(EngineError::NonZeroExit { code: Some(75), .. })
+        ));
+        assert_eq!(calls(&log).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn only_matching_failures_are_retried() {
+        let dir = scratch("not-retryable");
+        let log = dir.join("calls");
+        let retry = json!({ "max_attempts": 3, "backoff_ms": 1, "retry_exit_codes": [75], "retry_stderr": ["(?i)rate limit"] });
+        let mut wrong_code = sh(&log, "wrong-code", "echo bad input >&2; exit 2");
+        wrong_code["retry"] = retry.clone();
+        let mut rate_limited = sh(&log, "rate-limited", "echo 'Rate limit hit' >&2; exit 1");
+        rate_limited["retry"] = retry.clone();
+        // Exits 0 with nothing to extract
+        let mut empty = sh(&log, "empty", "true");
+        empty["retry"] = json!({ "max_attempts": 3, "backoff_ms": 1 });
+        let registry = registry(json!({
+            "wrong-code": wrong_code,
+            "rate-limited": rate_limited,
+            "empty": empty,
+            "missing": { "command": "mcp-council-no-such-cli", "retry": { "max_attempts": 3, "backoff_ms": 1 } }
+        }));
+
+        for engine in ["wrong-code", "rate-limited", "empty", "missing"] {
+            assert!(run_llm(&registry, engine, "q").await.is_err(), "{} answered", engine);
+        }
+        assert_eq!(
+            calls(&log),
+            ["wrong-code", "rate-limited", "rate-limited", "rate-limited", "empty"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fallbacks_run_in_order_after_retries() {
+        let dir = scratch("fallback");
+        let log = dir.join("calls");
+        let mut primary = sh(&log, "primary", "exit 75");
+        primary["retry"] = json!({ "max_attempts": 2, "backoff_ms": 1, "retry_exit_codes": [75] });
+        primary["fallback"] = json!(["second", "missing", "third", "fourth"]);
+        let registry = registry(json!({
+            "primary": primary,
+            "second": sh(&log, "second", "exit 1"),
+            "missing": { "command": "mcp-council-no-such-cli" },
+            "third": sh(&log, "third", "echo from third"),
+            "fourth": sh(&log, "fourth", "echo from fourth")
+        }));
+
+        let answer = run_llm(&registry, "primary", "q").await.unwrap();
+        assert_eq!(answer.text, "from third");
+        assert_eq!(answer.engine, "third");
+        assert_eq!(answer.attempts, 1);
+        assert_eq!(calls(&log), ["primary", "primary", "second", "third"]);
+    }
 }
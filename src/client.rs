@@ -0,0 +1,282 @@
+use crate::logging::Level;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+
+/// How long a sampling request may wait for the client, including any
+/// human-in-the-loop approval the client shows before running the model.
+const SAMPLING_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Upper bound on tokens requested for a review or synthesis.
+pub const SAMPLING_MAX_TOKENS: u32 = 8192;
+
+tokio::task_local! {
+    /// Id of the client request whose handler is running, so transports can
+    /// deliver related server-to-client messages alongside its response.
+    pub static ORIGIN: Option<String>;
+}
+
+/// A message queued for the client, tagged with the client request it
+/// belongs to. Responses to a request are marked `completes`; a cancelled
+/// request completes without a message.
+#[derive(Debug)]
+pub struct Outgoing {
+    pub origin: Option<String>,
+    pub message: Option<String>,
+    pub completes: bool,
+}
+
+/// A server-to-client request the client answered with a JSON-RPC error,
+/// or that could not be delivered.
+#[derive(Debug, Error)]
+pub enum ClientRequestError {
+    #[error("Client returned error {code} for {method}: {message}")]
+    Rejected {
+        method: String,
+        code: i64,
+        message: String,
+    },
+    #[error("Client did not answer {method} within {timeout_secs}s")]
+    TimedOut { method: String, timeout_secs: u64 },
+    #[error("Connection closed before the client answered {method}")]
+    Disconnected { method: String },
+}
+
+/// Text produced by the client's model for a `sampling/createMessage` request.
+#[derive(Debug, Clone)]
+pub struct SampledMessage {
+    pub text: String,
+    pub model: Option<String>,
+    pub stop_reason: Option<String>,
+}
+
+struct PendingRequest {
+    method: String,
+    waiter: oneshot::Sender<Result<Value, ClientRequestError>>,
+}
+
+struct PendingGuard<'a> {
+    peer: &'a ClientPeer,
+    id: &'a str,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.peer.pending.lock().unwrap().remove(self.id);
+    }
+}
+
+/// The client side of the connection as seen from the server: remembers the
+/// capabilities sent in `initialize`, issues server-to-client requests with
+/// their own ids, and resolves them when the matching response is read.
+pub struct ClientPeer {
+    outgoing: Mutex<Option<mpsc::UnboundedSender<Outgoing>>>,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<String, PendingRequest>>,
+    capabilities: Mutex<Value>,
+    /// Minimum level forwarded as `notifications/message`; nothing is
+    /// forwarded until the client sends `logging/setLevel`
+    log_level: Mutex<Option<Level>>,
+}
+
+impl ClientPeer {
+    pub fn new(outgoing: mpsc::UnboundedSender<Outgoing>) -> Self {
+        Self {
+            outgoing: Mutex::new(Some(outgoing)),
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+            capabilities: Mutex::new(Value::Null),
+            log_level: Mutex::new(None),
+        }
+    }
+
+    pub fn set_capabilities(&self, capabilities: Value) {
+        *self.capabilities.lock().unwrap() = capabilities;
+    }
+
+    pub fn supports_sampling(&self) -> bool {
+        self.capabilities.lock().unwrap().get("sampling").is_some()
+    }
+
+    pub fn set_log_level(&self, level: Level) {
+        *self.log_level.lock().unwrap() = Some(level);
+    }
+
+    /// Forward a log record to the client if it asked for this level.
+    pub fn log_message(&self, level: Level, logger: &str, message: &str) {
+        if self.log_level.lock().unwrap().is_some_and(|min| level >= min) {
+            let params = json!({ "level": level.as_str(), "logger": logger, "data": message });
+            // Failing to log is not worth reporting
+            let _ = self.notify("notifications/message", params);
+        }
+    }
+
+    fn queue(&self, outgoing: Outgoing) -> Result<()> {
+        let sender = self.outgoing.lock().unwrap();
+        let sender = sender.as_ref().context("Connection closed")?;
+        sender.send(outgoing).context("Connection closed")?;
+        Ok(())
+    }
+
+    /// Queue a server-initiated message, tagged with the request whose
+    /// handler sent it.
+    fn send(&self, message: String) -> Result<()> {
+        self.queue(Outgoing {
+            origin: ORIGIN.try_with(|o| o.clone()).ok().flatten(),
+            message: Some(message),
+            completes: false,
+        })
+    }
+
+    /// Queue the response that completes request `origin`, behind anything
+    /// its handler already sent. `None` completes it without a response.
+    /// Responses not tied to one request (batches, unparsable input) have
+    /// no origin.
+    pub fn complete(&self, origin: Option<String>, response: Option<String>) {
+        let queued = self.queue(Outgoing {
+            origin,
+            message: response,
+            completes: true,
+        });
+        if let Err(e) = queued {
+            log_error!("Failed to queue response: {}", e);
+        }
+    }
+
+    /// Stop accepting outgoing messages so the writer can drain and exit.
+    pub fn close(&self) {
+        self.outgoing.lock().unwrap().take();
+        self.cancel_pending();
+    }
+
+    /// Fail every request still waiting for the client with `Disconnected`.
+    pub fn cancel_pending(&self) {
+        for (_, pending) in self.pending.lock().unwrap().drain() {
+            let _ = pending.waiter.send(Err(ClientRequestError::Disconnected {
+                method: pending.method,
+            }));
+        }
+    }
+
+    /// Send a notification to the client; no response is expected.
+    pub fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+        self.send(serde_json::to_string(&message)?)
+    }
+
+    /// Send a request to the client and wait for its response.
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = format!("council-{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(
+            id.clone(),
+            PendingRequest {
+                method: method.to_string(),
+                waiter: tx,
+            },
+        );
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        // Forget the request however this call ends, including when the
+        // calling handler is cancelled while waiting
+        let _pending = PendingGuard { peer: self, id: &id };
+        log_debug!("Sending client request: {} ({})", method, id);
+        self.send(serde_json::to_string(&message)?)?;
+
+        match tokio::time::timeout(SAMPLING_TIMEOUT, rx).await {
+            Ok(Ok(result)) => Ok(result?),
+            Ok(Err(_)) => Err(ClientRequestError::Disconnected {
+                method: method.to_string(),
+            }
+            .into()),
+            Err(_) => Err(ClientRequestError::TimedOut {
+                method: method.to_string(),
+                timeout_secs: SAMPLING_TIMEOUT.as_secs(),
+            }
+            .into()),
+        }
+    }
+
+    /// Route a response from the client to the request waiting on it.
+    /// Returns false when the message is not a response to one of ours.
+    pub fn handle_response(&self, message: &Value) -> bool {
+        if message.get("method").is_some()
+            || (message.get("result").is_none() && message.get("error").is_none())
+        {
+            return false;
+        }
+        let id = match &message["id"] {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => return false,
+        };
+        let Some(pending) = self.pending.lock().unwrap().remove(&id) else {
+            log_warn!("Response for unknown client request id {}", id);
+            return true;
+        };
+
+        let outcome = match message.get("error") {
+            Some(error) => Err(ClientRequestError::Rejected {
+                method: pending.method,
+                code: error["code"].as_i64().unwrap_or(0),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            }),
+            None => Ok(message["result"].clone()),
+        };
+        let _ = pending.waiter.send(outcome);
+        true
+    }
+
+    /// Ask the client's model to complete a single-turn prompt via
+    /// `sampling/createMessage`, preferring `model_hint` when the client can.
+    pub async fn create_message(
+        &self,
+        prompt: &str,
+        model_hint: &str,
+        max_tokens: u32,
+    ) -> Result<SampledMessage> {
+        let result = self
+            .request(
+                "sampling/createMessage",
+                json!({
+                    "messages": [
+                        {
+                            "role": "user",
+                            "content": { "type": "text", "text": prompt }
+                        }
+                    ],
+                    "modelPreferences": {
+                        "hints": [{ "name": model_hint }],
+                        "intelligencePriority": 0.8
+                    },
+                    "includeContext": "none",
+                    "maxTokens": max_tokens
+                }),
+            )
+            .await?;
+
+        let text = result["content"]["text"]
+            .as_str()
+            .filter(|t| !t.trim().is_empty())
+            .context("Sampling result has no text content")?;
+        Ok(SampledMessage {
+            text: text.to_string(),
+            model: result["model"].as_str().map(|s| s.to_string()),
+            stop_reason: result["stopReason"].as_str().map(|s| s.to_string()),
+        })
+    }
+}
@@ -0,0 +1,23 @@
+// Embed command files at compile time
+const CMD_FINALIZE: &str = include_str!("../commands/cc/finalize.md");
+const CMD_FIRST_ANSWER: &str = include_str!("../commands/cc/first_answer.md");
+const CMD_PEER_REVIEW: &str = include_str!("../commands/cc/peer_review.md");
+const CMD_SAVE_FINAL: &str = include_str!("../commands/cc/save_final.md");
+const CMD_SAVE_REVIEW: &str = include_str!("../commands/cc/save_review.md");
+const CMD_SAVE_SUMMARY: &str = include_str!("../commands/cc/save_summary.md");
+const CMD_STATUS: &str = include_str!("../commands/cc/status.md");
+const CMD_SUMMARIZE: &str = include_str!("../commands/cc/summarize.md");
+const CMD_TALLY: &str = include_str!("../commands/cc/tally.md");
+
+/// Slash-command files installed by `--init` and served as MCP prompts.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("finalize.md", CMD_FINALIZE),
+    ("first_answer.md", CMD_FIRST_ANSWER),
+    ("peer_review.md", CMD_PEER_REVIEW),
+    ("save_final.md", CMD_SAVE_FINAL),
+    ("save_review.md", CMD_SAVE_REVIEW),
+    ("save_summary.md", CMD_SAVE_SUMMARY),
+    ("status.md", CMD_STATUS),
+    ("summarize.md", CMD_SUMMARIZE),
+    ("tally.md", CMD_TALLY),
+];
@@ -0,0 +1,90 @@
+use crate::engines::EngineConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Overrides the config file location.
+pub const CONFIG_ENV: &str = "MCP_COUNCIL_CONFIG";
+
+/// User-level settings, read from `$MCP_COUNCIL_CONFIG`, else
+/// `$XDG_CONFIG_HOME/mcp-council/config.json`, else
+/// `~/.config/mcp-council/config.json`. A missing file means defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Council root used when `MCP_COUNCIL_DIR` is unset and no project-local
+    /// `.council` applies. `~` expands to the home directory.
+    pub council_dir: Option<String>,
+    /// Look for a `.council` directory in the working directory and its
+    /// parents before falling back to `council_dir` / `~/.council`.
+    pub project_local: bool,
+    /// Minimum level written to stderr and the log file (`debug`, `info`,
+    /// `warning`, ...). `MCP_COUNCIL_LOG` takes precedence. Defaults to `info`.
+    pub log_level: Option<String>,
+    /// Also write the log to `logs/mcp-council.log` under the council root,
+    /// rotated at 5 MiB.
+    pub log_file: bool,
+    /// Log request and response bodies as they are instead of redacted.
+    pub log_payloads: bool,
+    /// Model CLIs and HTTP endpoints for `run_llm`, by engine name. Entries
+    /// replace the built-in engine of the same name.
+    pub engines: BTreeMap<String, EngineConfig>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = env::var(CONFIG_ENV) {
+            if !path.trim().is_empty() {
+                return Some(expand_home(path.trim()));
+            }
+        }
+        let config_home = env::var("XDG_CONFIG_HOME")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|h| h.join(".config")))?;
+        Some(config_home.join("mcp-council").join("config.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read config file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .context(format!("Failed to parse config file: {}", path.display()))
+    }
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var("HOME")
+        .ok()
+        .filter(|h| !h.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expand a leading `~` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    if path == "~" {
+        if let Some(home) = home_dir() {
+            return home;
+        }
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
@@ -0,0 +1,794 @@
+//! JSON-RPC and MCP conformance checks, driving `McpServer::serve` over
+//! in-memory pipes exactly as a stdio client would.
+
+use crate::config::CONFIG_ENV;
+use crate::council_dir::COUNCIL_DIR_ENV;
+use crate::mcp::McpServer;
+use serde_json::{json, Value};
+use std::sync::{Arc, Once};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, Lines};
+use tokio::task::JoinHandle;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Point every test at a scratch council root and config file instead of
+/// `~/.council` and the user's config. The config file does not exist until
+/// a test writes one.
+pub(crate) fn council_root() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let scratch = std::env::temp_dir().join(format!("mcp-council-conformance-{}", std::process::id()));
+        std::env::set_var(COUNCIL_DIR_ENV, &scratch);
+        std::env::set_var(CONFIG_ENV, scratch.with_extension("config.json"));
+    });
+}
+
+struct Client {
+    input: DuplexStream,
+    output: Lines<BufReader<DuplexStream>>,
+    server: JoinHandle<anyhow::Result<()>>,
+}
+
+impl Client {
+    fn connect() -> Self {
+        council_root();
+        let (input, server_input) = tokio::io::duplex(1 << 16);
+        let (server_output, output) = tokio::io::duplex(1 << 16);
+        let server = Arc::new(McpServer::new());
+        Self {
+            input,
+            output: BufReader::new(output).lines(),
+            server: tokio::spawn(server.serve(server_input, server_output)),
+        }
+    }
+
+    /// Connect and complete the `initialize` handshake.
+    async fn initialized(capabilities: Value) -> Self {
+        let mut client = Self::connect();
+        let response = client
+            .request(
+                0,
+                "initialize",
+                json!({ "protocolVersion": "2025-06-18", "capabilities": capabilities }),
+            )
+            .await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-06-18");
+        client
+            .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await;
+        client
+    }
+
+    async fn send_raw(&mut self, line: &str) {
+        self.input.write_all(line.as_bytes()).await.unwrap();
+        self.input.write_all(b"\n").await.unwrap();
+    }
+
+    async fn send(&mut self, message: Value) {
+        self.send_raw(&message.to_string()).await;
+    }
+
+    async fn recv(&mut self) -> Value {
+        let line = tokio::time::timeout(TIMEOUT, self.output.next_line())
+            .await
+            .expect("timed out waiting for the server")
+            .unwrap()
+            .expect("server closed the connection");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    async fn request(&mut self, id: i64, method: &str, params: Value) -> Value {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await;
+        let response = self.recv().await;
+        assert_eq!(response["id"], id, "unexpected message: {}", response);
+        response
+    }
+
+    async fn call_tool(&mut self, id: i64, name: &str, arguments: Value) -> Value {
+        self.request(id, "tools/call", json!({ "name": name, "arguments": arguments }))
+            .await
+    }
+
+    /// Close the input and wait for the server to drain and stop.
+    async fn close(self) {
+        drop(self.input);
+        tokio::time::timeout(TIMEOUT, self.server)
+            .await
+            .expect("server did not stop after EOF")
+            .unwrap()
+            .unwrap();
+    }
+}
+
+fn error_code(response: &Value) -> i64 {
+    response["error"]["code"]
+        .as_i64()
+        .unwrap_or_else(|| panic!("expected an error: {}", response))
+}
+
+#[tokio::test]
+async fn unparsable_input_is_a_parse_error() {
+    let mut client = Client::connect();
+    client.send_raw("{not json").await;
+    let response = client.recv().await;
+    assert_eq!(error_code(&response), -32700);
+    assert_eq!(response["id"], Value::Null);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn malformed_requests_are_invalid() {
+    let mut client = Client::connect();
+    let cases = [
+        (json!(1), Value::Null),
+        (json!({ "id": 1, "method": "ping" }), json!(1)),
+        (json!({ "jsonrpc": "2.0", "id": 2, "method": 7 }), json!(2)),
+        (json!({ "jsonrpc": "2.0", "id": true, "method": "ping" }), Value::Null),
+        (json!({ "jsonrpc": "2.0", "id": null, "method": "ping" }), Value::Null),
+        (json!({ "jsonrpc": "2.0", "id": 3, "method": "ping", "params": "x" }), json!(3)),
+    ];
+    for (message, id) in cases {
+        client.send(message.clone()).await;
+        let response = client.recv().await;
+        assert_eq!(error_code(&response), -32600, "for {}", message);
+        assert_eq!(response["id"], id, "for {}", message);
+    }
+    client.close().await;
+}
+
+#[tokio::test]
+async fn ping_returns_an_empty_result() {
+    let mut client = Client::connect();
+    let response = client.request(1, "ping", json!({})).await;
+    assert_eq!(response["result"], json!({}));
+    client.close().await;
+}
+
+#[tokio::test]
+async fn unknown_methods_are_not_found() {
+    let mut client = Client::initialized(json!({})).await;
+    let response = client.request(1, "council/unknown", json!({})).await;
+    assert_eq!(error_code(&response), -32601);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn notifications_get_no_response() {
+    let mut client = Client::initialized(json!({})).await;
+    client
+        .send(json!({ "jsonrpc": "2.0", "method": "ping" }))
+        .await;
+    client
+        .send(json!({ "jsonrpc": "2.0", "method": "council/unknown" }))
+        .await;
+    // The next line on the wire answers this request
+    let response = client.request(7, "ping", json!({})).await;
+    assert_eq!(response["result"], json!({}));
+    client.close().await;
+}
+
+#[tokio::test]
+async fn batches_are_answered_with_one_array() {
+    let mut client = Client::initialized(json!({})).await;
+    client
+        .send(json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "ping" },
+            { "jsonrpc": "2.0", "method": "notifications/initialized" },
+            { "jsonrpc": "2.0", "id": "two", "method": "tools/list" },
+            { "jsonrpc": "2.0", "id": 3, "method": 3 }
+        ]))
+        .await;
+    let response = client.recv().await;
+    let responses = response.as_array().expect("batch response is an array");
+    assert_eq!(responses.len(), 3);
+    let by_id = |id: Value| responses.iter().find(|r| r["id"] == id).unwrap();
+    assert_eq!(by_id(json!(1))["result"], json!({}));
+    assert!(by_id(json!("two"))["result"]["tools"].is_array());
+    assert_eq!(error_code(by_id(json!(3))), -32600);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn empty_and_notification_only_batches() {
+    let mut client = Client::initialized(json!({})).await;
+    client.send_raw("[]").await;
+    let response = client.recv().await;
+    assert_eq!(error_code(&response), -32600);
+
+    client
+        .send(json!([{ "jsonrpc": "2.0", "method": "notifications/initialized" }]))
+        .await;
+    let response = client.request(5, "ping", json!({})).await;
+    assert_eq!(response["result"], json!({}));
+    client.close().await;
+}
+
+#[tokio::test]
+async fn initialize_negotiates_the_protocol_version() {
+    for (requested, expected) in [
+        ("2025-06-18", "2025-06-18"),
+        ("2026-01-01", "2025-06-18"),
+        ("2025-04-01", "2025-03-26"),
+        ("2024-11-05", "2024-11-05"),
+        ("2023-01-01", "2025-06-18"),
+    ] {
+        let mut client = Client::connect();
+        let response = client
+            .request(1, "initialize", json!({ "protocolVersion": requested, "capabilities": {} }))
+            .await;
+        let result = &response["result"];
+        assert_eq!(result["protocolVersion"], expected, "for {}", requested);
+        assert_eq!(result["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
+
+        let tools = client.request(2, "tools/list", json!({})).await;
+        let structured = expected >= "2025-06-18";
+        assert_eq!(
+            tools["result"]["tools"][0].get("outputSchema").is_some(),
+            structured,
+            "for {}",
+            requested
+        );
+        client.close().await;
+    }
+}
+
+#[tokio::test]
+async fn prompts_are_listed_and_rendered() {
+    let mut client = Client::initialized(json!({})).await;
+
+    let response = client.request(1, "prompts/list", json!({})).await;
+    let names: Vec<&str> = response["result"]["prompts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"save_final"), "{:?}", names);
+
+    let response = client
+        .request(
+            2,
+            "prompts/get",
+            json!({ "name": "save_final", "arguments": { "title": "demo", "content": "All done." } }),
+        )
+        .await;
+    let result = &response["result"];
+    assert!(!result["description"].as_str().unwrap().is_empty());
+    let messages = result["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["role"], "user");
+    assert_eq!(messages[0]["content"]["type"], "text");
+    let text = messages[0]["content"]["text"].as_str().unwrap();
+    assert!(text.contains("council.save_final"), "{}", text);
+    assert!(text.ends_with("Arguments:\n- `title`: demo\n- `content`: All done."), "{}", text);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn bad_prompt_requests_are_invalid_params() {
+    let mut client = Client::initialized(json!({})).await;
+
+    // Blank counts as missing
+    let response = client
+        .request(1, "prompts/get", json!({ "name": "save_final", "arguments": { "title": "demo", "content": " " } }))
+        .await;
+    assert_eq!(error_code(&response), -32602);
+    assert_eq!(response["error"]["data"], json!({ "prompt": "save_final", "param": "content" }));
+
+    let response = client.request(2, "prompts/get", json!({ "name": "no_such_prompt" })).await;
+    assert_eq!(error_code(&response), -32602);
+    assert_eq!(response["error"]["data"], json!({ "param": "name", "value": "no_such_prompt" }));
+
+    let response = client.request(3, "prompts/get", json!({})).await;
+    assert_eq!(error_code(&response), -32602);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn tool_arguments_are_validated() {
+    let mut client = Client::initialized(json!({})).await;
+    let response = client
+        .call_tool(1, "council.summarize", json!({ "title": "t", "max_length": "100" }))
+        .await;
+    assert_eq!(error_code(&response), -32602);
+    let errors = response["error"]["data"]["errors"].as_array().unwrap();
+    let properties: Vec<&str> = errors.iter().filter_map(|e| e["property"].as_str()).collect();
+    assert!(properties.contains(&"content"));
+    assert!(properties.contains(&"max_length"));
+
+    let response = client.request(2, "tools/call", json!({})).await;
+    assert_eq!(error_code(&response), -32602);
+
+    let response = client.call_tool(3, "council.status", json!({ "title": "../x" })).await;
+    assert_eq!(error_code(&response), -32602);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn tool_failures_are_error_results() {
+    let mut client = Client::initialized(json!({})).await;
+    let response = client
+        .call_tool(1, "council.tally", json!({ "title": "conformance-missing" }))
+        .await;
+    assert_eq!(response["result"]["isError"], true);
+    assert!(response["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Directory not found"));
+    client.close().await;
+}
+
+#[tokio::test]
+async fn tool_results_carry_structured_content() {
+    let mut client = Client::initialized(json!({})).await;
+    let response = client
+        .call_tool(
+            1,
+            "council.first_answer",
+            json!({ "title": "conformance-structured", "model": "gpt", "prompt": "q?", "content": "a" }),
+        )
+        .await;
+    let result = &response["result"];
+    let text: Value = serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(result["structuredContent"], text);
+    assert_eq!(text["success"], true);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn cancelled_requests_get_no_response() {
+    let mut client = Client::initialized(json!({ "sampling": {} })).await;
+    let title = "conformance-cancel";
+    for (id, model) in [(1, "gpt"), (2, "gemini")] {
+        let response = client
+            .call_tool(
+                id,
+                "council.first_answer",
+                json!({ "title": title, "model": model, "prompt": "q?", "content": model }),
+            )
+            .await;
+        assert_eq!(response["result"]["structuredContent"]["success"], true);
+    }
+
+    // peer_review waits on the sampling request until it is cancelled
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": {
+                "name": "council.peer_review",
+                "arguments": { "title": title, "model": "claude" }
+            }
+        }))
+        .await;
+    let sampling = client.recv().await;
+    assert_eq!(sampling["method"], "sampling/createMessage");
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": 3, "reason": "test" }
+        }))
+        .await;
+
+    let response = client.request(4, "ping", json!({})).await;
+    assert_eq!(response["result"], json!({}));
+    client.close().await;
+}
+
+/// Save one Stage1 answer per model.
+async fn answered(client: &mut Client, title: &str, models: &[&str]) {
+    for (idx, model) in models.iter().enumerate() {
+        let response = client
+            .call_tool(
+                100 + idx as i64,
+                "council.first_answer",
+                json!({ "title": title, "model": model, "prompt": "q?", "content": format!("{} says hi", model) }),
+            )
+            .await;
+        assert_eq!(response["result"]["structuredContent"]["success"], true, "{}", response);
+    }
+}
+
+/// Call a tool that asks for sampling, answer the `sampling/createMessage`
+/// request with `reply` (a result or an error object) and return the tool's
+/// structured result.
+async fn call_with_sampling(client: &mut Client, id: i64, name: &str, arguments: Value, reply: Value) -> Value {
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments }
+        }))
+        .await;
+    let sampling = client.recv().await;
+    assert_eq!(sampling["method"], "sampling/createMessage", "{}", sampling);
+    assert_eq!(sampling["params"]["messages"][0]["role"], "user");
+
+    let mut response = json!({ "jsonrpc": "2.0", "id": sampling["id"] });
+    let key = if reply.get("code").is_some() { "error" } else { "result" };
+    response[key] = reply;
+    client.send(response).await;
+
+    let response = client.recv().await;
+    assert_eq!(response["id"], id, "unexpected message: {}", response);
+    response["result"]["structuredContent"].clone()
+}
+
+fn sampled(text: &str) -> Value {
+    json!({
+        "role": "assistant",
+        "content": { "type": "text", "text": text },
+        "model": "client-model",
+        "stopReason": "endTurn"
+    })
+}
+
+async fn read_manifest(client: &mut Client, id: i64, title: &str) -> Value {
+    let response = client
+        .request(id, "resources/read", json!({ "uri": format!("council://{}/manifest", title) }))
+        .await;
+    serde_json::from_str(response["result"]["contents"][0]["text"].as_str().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn sampled_reviews_and_finals_are_saved() {
+    let mut client = Client::initialized(json!({ "sampling": {} })).await;
+    let title = "conformance-sampling";
+    answered(&mut client, title, &["gpt", "gemini"]).await;
+
+    let review_text = "Both fine.\n\nFINAL RANKING:\n1. Response A\n";
+    let review = call_with_sampling(
+        &mut client,
+        1,
+        "council.peer_review",
+        json!({ "title": title, "model": "gemini", "self_model": "gemini" }),
+        sampled(review_text),
+    )
+    .await;
+    assert_eq!(review["action"], "peer_review_saved", "{}", review);
+    assert_eq!(review["sampled_model"], "client-model");
+    assert_eq!(review["stop_reason"], "endTurn");
+    assert_eq!(review["saved"]["success"], true);
+    let review_path = review["saved"]["file_saved"].as_str().unwrap();
+    assert!(review_path.ends_with("peer-review-by-gemini.md"), "{}", review_path);
+    assert!(std::fs::read_to_string(review_path).unwrap().contains(review_text));
+
+    let final_answer = call_with_sampling(
+        &mut client,
+        2,
+        "council.finalize",
+        json!({ "title": title, "model": "claude" }),
+        sampled("The council agrees."),
+    )
+    .await;
+    assert_eq!(final_answer["action"], "final_answer_saved", "{}", final_answer);
+    assert_eq!(final_answer["sampled_model"], "client-model");
+    assert_eq!(final_answer["stop_reason"], "endTurn");
+    let final_path = final_answer["saved"]["file_saved"].as_str().unwrap();
+    assert!(final_path.ends_with("final-answer-by-claude.md"), "{}", final_path);
+    assert!(std::fs::read_to_string(final_path).unwrap().ends_with("The council agrees."));
+
+    let manifest = read_manifest(&mut client, 3, title).await;
+    let reviews = manifest["reviews"].as_array().unwrap();
+    assert_eq!(reviews.len(), 1, "{}", manifest);
+    assert_eq!(reviews[0]["reviewer"], "gemini");
+    assert!(reviews[0]["saved_at"].is_string(), "{}", manifest);
+    assert_eq!(manifest["finals"][0]["file"], "final-answer-by-claude.md");
+    assert_eq!(manifest["stages"]["stage3"]["status"], "complete");
+    client.close().await;
+}
+
+#[tokio::test]
+async fn every_stage_names_files_after_the_same_model() {
+    let mut client = Client::initialized(json!({})).await;
+    let title = "conformance-file-names";
+    let steps = [
+        ("council.first_answer", json!({ "title": title, "model": "GPT 5.1", "prompt": "q?", "content": "a" })),
+        ("council.save_review", json!({ "title": title, "model": "GPT 5.1", "content": "r" })),
+        ("council.save_final", json!({ "title": title, "model": "GPT 5.1", "content": "f" })),
+    ];
+    let mut saved = Vec::new();
+    for (id, (name, arguments)) in steps.into_iter().enumerate() {
+        let response = client.call_tool(id as i64 + 1, name, arguments).await;
+        let path = response["result"]["structuredContent"]["file_saved"].as_str().unwrap().to_string();
+        saved.push(path.rsplit(['/', '\\']).next().unwrap().to_string());
+    }
+    assert_eq!(saved, ["gpt-5-1-answer.md", "peer-review-by-gpt-5-1.md", "final-answer-by-gpt-5-1.md"]);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn failed_sampling_returns_the_prompt() {
+    let mut client = Client::initialized(json!({ "sampling": {} })).await;
+    let title = "conformance-sampling-error";
+    answered(&mut client, title, &["gpt", "gemini"]).await;
+
+    let review = call_with_sampling(
+        &mut client,
+        1,
+        "council.peer_review",
+        json!({ "title": title, "model": "gemini", "self_model": "gemini" }),
+        json!({ "code": -1, "message": "User rejected sampling request" }),
+    )
+    .await;
+    assert_eq!(review["action"], "perform_peer_review_and_save", "{}", review);
+    assert!(review["review_request"].as_str().unwrap().contains("FINAL RANKING"));
+    let error = review["sampling_error"].as_str().unwrap();
+    assert!(error.contains("User rejected sampling request"), "{}", error);
+
+    let manifest = read_manifest(&mut client, 2, title).await;
+    assert!(manifest["reviews"][0]["saved_at"].is_null(), "{}", manifest);
+    let base_dir = crate::council_dir::find_council_dir().unwrap().join(title);
+    assert!(!base_dir.join("peer-review-by-gemini.md").exists());
+    client.close().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn parallel_tool_calls_keep_every_manifest_entry() {
+    let mut client = Client::initialized(json!({})).await;
+    let title = "conformance-parallel";
+    let models: Vec<String> = (0..12).map(|i| format!("model{}", i)).collect();
+    for (id, model) in models.iter().enumerate() {
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": id + 1,
+                "method": "tools/call",
+                "params": {
+                    "name": "council.first_answer",
+                    "arguments": { "title": title, "model": model, "prompt": "q?", "content": "a".repeat(20_000) }
+                }
+            }))
+            .await;
+    }
+    for _ in &models {
+        let response = client.recv().await;
+        assert_eq!(response["result"]["structuredContent"]["success"], true, "{}", response);
+    }
+
+    let response = client
+        .request(100, "resources/read", json!({ "uri": format!("council://{}/manifest", title) }))
+        .await;
+    let manifest: Value =
+        serde_json::from_str(response["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+    let recorded: Vec<&str> = manifest["answers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["model"].as_str().unwrap())
+        .collect();
+    assert_eq!(recorded.len(), models.len(), "recorded: {:?}", recorded);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn status_reports_untracked_answers_without_writing() {
+    let mut client = Client::initialized(json!({})).await;
+    let title = "conformance-untracked";
+    let base_dir = crate::council_dir::find_council_dir().unwrap().join(title);
+    std::fs::create_dir_all(&base_dir).unwrap();
+    for model in ["gpt", "gemini"] {
+        std::fs::write(
+            base_dir.join(format!("{}-answer.md", model)),
+            format!("# {m} answer\n- model: {m}\n- prompt: q?\n- created_at: now\n\n{m} says hi\n", m = model),
+        )
+        .unwrap();
+    }
+
+    let response = client.call_tool(1, "council.status", json!({ "title": title })).await;
+    let status = &response["result"]["structuredContent"];
+    assert_eq!(status["ready_for_finalize"], false);
+    assert_eq!(status["untracked_answers"], json!([]));
+    assert_eq!(status["answered"].as_array().unwrap().len(), 2);
+    assert!(!base_dir.join("council.json").exists(), "status wrote the manifest");
+
+    // Recorded by a tool that saves, then a third answer appears by hand
+    client
+        .call_tool(2, "council.first_answer", json!({ "title": title, "model": "claude", "prompt": "q?", "content": "c" }))
+        .await;
+    std::fs::write(
+        base_dir.join("grok-answer.md"),
+        "# grok answer\n- model: grok\n- prompt: q?\n- created_at: now\n\ngrok says hi\n",
+    )
+    .unwrap();
+    let response = client.call_tool(3, "council.status", json!({ "title": title })).await;
+    let status = &response["result"]["structuredContent"];
+    assert_eq!(status["untracked_answers"], json!(["grok-answer.md"]));
+    assert_eq!(status["ready_for_finalize"], false);
+    let steps = status["next_steps"].to_string();
+    assert!(
+        steps.contains(&format!(
+            "Record the untracked answer file(s) grok-answer.md by running peer_review {} by <model>",
+            title
+        )),
+        "{}",
+        steps
+    );
+    assert!(!steps.contains("tally"), "{}", steps);
+
+    // Following the advice records the file
+    client
+        .call_tool(4, "council.peer_review", json!({ "title": title, "model": "gemini", "self_model": "gemini" }))
+        .await;
+    let response = client.call_tool(5, "council.status", json!({ "title": title })).await;
+    assert_eq!(response["result"]["structuredContent"]["untracked_answers"], json!([]));
+    client.close().await;
+}
+
+#[tokio::test]
+async fn save_final_reports_the_inputs_finalize_used() {
+    let mut client = Client::initialized(json!({})).await;
+    let title = "conformance-based-on";
+    let review = "Looks fine.\n\nFINAL RANKING:\n1. Response A\n";
+    let steps = [
+        ("council.first_answer", json!({ "title": title, "model": "gpt", "prompt": "q?", "content": "g" })),
+        ("council.first_answer", json!({ "title": title, "model": "gemini", "prompt": "q?", "content": "m" })),
+        ("council.save_review", json!({ "title": title, "model": "gemini", "content": review })),
+        ("council.finalize", json!({ "title": title, "model": "claude" })),
+        // Arrives after the chairman prompt was built
+        ("council.save_review", json!({ "title": title, "model": "gpt", "content": review })),
+        ("council.save_final", json!({ "title": title, "model": "claude", "content": "final" })),
+    ];
+    let mut last = Value::Null;
+    for (id, (name, arguments)) in steps.into_iter().enumerate() {
+        let response = client.call_tool(id as i64 + 1, name, arguments).await;
+        last = response["result"]["structuredContent"].clone();
+        assert_eq!(last["success"], true, "{}: {}", name, response);
+    }
+
+    let files = |inputs: &Value| -> Vec<String> {
+        inputs
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["file"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(files(&last["based_on"]["stage1_answers"]), ["gemini-answer.md", "gpt-answer.md"]);
+    assert_eq!(files(&last["based_on"]["stage2_reviews"]), ["peer-review-by-gemini.md"]);
+    let saved = std::fs::read_to_string(last["file_saved"].as_str().unwrap()).unwrap();
+    assert!(!saved.contains("peer-review-by-gpt.md"), "{}", saved);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn resources_do_not_write_the_manifest() {
+    let mut client = Client::initialized(json!({})).await;
+    let title = "conformance-resources-readonly";
+    let base_dir = crate::council_dir::find_council_dir().unwrap().join(title);
+    std::fs::create_dir_all(&base_dir).unwrap();
+    std::fs::write(
+        base_dir.join("gpt-answer.md"),
+        "# gpt answer\n- model: gpt\n- prompt: q?\n- created_at: now\n\nhi\n",
+    )
+    .unwrap();
+
+    let response = client.request(1, "resources/list", json!({})).await;
+    let uris = response["result"]["resources"].to_string();
+    assert!(uris.contains(&format!("council://{}/answers/gpt", title)), "{}", uris);
+    let response = client
+        .request(2, "resources/read", json!({ "uri": format!("council://{}/manifest", title) }))
+        .await;
+    let manifest: Value =
+        serde_json::from_str(response["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(manifest["answers"][0]["model"], "gpt");
+    assert!(!base_dir.join("council.json").exists(), "resources wrote the manifest");
+    client.close().await;
+}
+
+/// Read up to the response to `id`, collecting the `method` notifications
+/// sent before it.
+async fn notified_until(client: &mut Client, id: i64, method: &str) -> (Vec<Value>, Value) {
+    let mut notifications = Vec::new();
+    loop {
+        let message = client.recv().await;
+        if message["method"] == method {
+            notifications.push(message);
+        } else {
+            assert_eq!(message["id"], id, "unexpected message: {}", message);
+            return (notifications, message);
+        }
+    }
+}
+
+#[tokio::test]
+async fn progress_is_reported_against_the_callers_token() {
+    let mut client = Client::initialized(json!({})).await;
+    let title = "conformance-progress";
+    answered(&mut client, title, &["gpt", "gemini"]).await;
+    let review = "Fine.\n\nFINAL RANKING:\n1. Response A\n";
+    for (id, model) in [(1, "gpt"), (2, "gemini")] {
+        let response = client
+            .call_tool(id, "council.save_review", json!({ "title": title, "model": model, "content": review }))
+            .await;
+        assert_eq!(response["result"]["structuredContent"]["success"], true, "{}", response);
+    }
+
+    let call = |id: i64, meta: Value| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {
+                "name": "council.finalize",
+                "arguments": { "title": title, "model": "claude" },
+                "_meta": meta
+            }
+        })
+    };
+    client.send(call(3, json!({ "progressToken": "finalize-1" }))).await;
+    let (progress, response) = notified_until(&mut client, 3, "notifications/progress").await;
+    assert_eq!(response["result"]["structuredContent"]["success"], true, "{}", response);
+
+    // Answers, two reviews and the aggregation
+    assert_eq!(progress.len(), 4, "{:?}", progress);
+    for (idx, notification) in progress.iter().enumerate() {
+        let params = &notification["params"];
+        assert_eq!(params["progressToken"], "finalize-1");
+        assert_eq!(params["progress"], idx as u64 + 1);
+        assert_eq!(params["total"], 4);
+        assert!(!params["message"].as_str().unwrap().is_empty());
+    }
+
+    // Numeric tokens are echoed as numbers; without a token nothing is sent
+    client.send(call(4, json!({ "progressToken": 7 }))).await;
+    let (progress, _) = notified_until(&mut client, 4, "notifications/progress").await;
+    assert_eq!(progress[0]["params"]["progressToken"], 7);
+    client.send(call(5, json!({}))).await;
+    let response = client.recv().await;
+    assert_eq!(response["id"], 5, "unexpected message: {}", response);
+    client.close().await;
+}
+
+#[tokio::test]
+async fn log_messages_follow_the_requested_level() {
+    let mut client = Client::initialized(json!({})).await;
+    let response = client.request(1, "logging/setLevel", json!({ "level": "verbose" })).await;
+    assert_eq!(error_code(&response), -32602);
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 2, "method": "logging/setLevel", "params": { "level": "debug" } }))
+        .await;
+    let (_, response) = notified_until(&mut client, 2, "notifications/message").await;
+    assert_eq!(response["result"], json!({}));
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": {
+                "name": "council.first_answer",
+                "arguments": {
+                    "title": "conformance-logging",
+                    "model": "gpt",
+                    "prompt": "secret question",
+                    "content": "secret answer"
+                }
+            }
+        }))
+        .await;
+    let (logged, response) = notified_until(&mut client, 3, "notifications/message").await;
+    assert_eq!(response["result"]["structuredContent"]["success"], true);
+    assert!(!logged.is_empty());
+    for message in &logged {
+        assert_eq!(message["params"]["level"], "debug");
+        assert!(!message.to_string().contains("secret"), "payload leaked: {}", message);
+    }
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 4, "method": "logging/setLevel", "params": { "level": "error" } }))
+        .await;
+    notified_until(&mut client, 4, "notifications/message").await;
+    // Only debug records are made here, so nothing precedes the response
+    let response = client
+        .call_tool(5, "council.status", json!({ "title": "conformance-logging" }))
+        .await;
+    assert_eq!(response["result"]["structuredContent"]["success"], true);
+    client.close().await;
+}
@@ -0,0 +1,234 @@
+use crate::config::{expand_home, home_dir, Config};
+use anyhow::{Context, Result};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Points every tool at an explicit council root.
+pub const COUNCIL_DIR_ENV: &str = "MCP_COUNCIL_DIR";
+
+/// Enables project-local `.council` discovery without a config file.
+pub const PROJECT_LOCAL_ENV: &str = "MCP_COUNCIL_PROJECT_LOCAL";
+
+/// Where the council root came from, in resolution order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootSource {
+    Env,
+    ProjectLocal,
+    Config,
+    Home,
+}
+
+impl fmt::Display for RootSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RootSource::Env => COUNCIL_DIR_ENV,
+            RootSource::ProjectLocal => "project-local .council",
+            RootSource::Config => "config file council_dir",
+            RootSource::Home => "~/.council",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Resolve the council root without touching the filesystem:
+///
+/// 1. `MCP_COUNCIL_DIR`
+/// 2. a `.council` directory in the working directory or one of its parents,
+///    when `project_local` is set in the config or `MCP_COUNCIL_PROJECT_LOCAL=1`
+/// 3. `council_dir` from the config file
+/// 4. `~/.council`
+///
+/// Project-local discovery comes before `council_dir` on purpose: it is
+/// opt-in, and a repository that keeps its own `.council` should win over a
+/// user-wide default.
+pub fn resolve_council_root() -> Result<(PathBuf, RootSource)> {
+    if let Ok(dir) = env::var(COUNCIL_DIR_ENV) {
+        if !dir.trim().is_empty() {
+            return Ok((absolute(expand_home(dir.trim()))?, RootSource::Env));
+        }
+    }
+
+    let config = Config::load()?;
+    let project_cwd = if project_local_enabled(&config, env::var(PROJECT_LOCAL_ENV).ok().as_deref()) {
+        Some(env::current_dir()?)
+    } else {
+        None
+    };
+    let home_council = home_dir().map(|h| h.join(".council"));
+    resolve_configured(&config, project_cwd.as_deref(), home_council.as_deref())
+}
+
+fn project_local_enabled(config: &Config, env_value: Option<&str>) -> bool {
+    config.project_local || env_value.is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// Steps 2-4 of `resolve_council_root`. Project-local discovery starts at
+/// `project_cwd` when it is enabled.
+fn resolve_configured(
+    config: &Config,
+    project_cwd: Option<&Path>,
+    home_council: Option<&Path>,
+) -> Result<(PathBuf, RootSource)> {
+    if let Some(cwd) = project_cwd {
+        if let Some(dir) = discover_project_council(cwd, home_council) {
+            return Ok((dir, RootSource::ProjectLocal));
+        }
+    }
+
+    if let Some(dir) = config.council_dir.as_deref().filter(|d| !d.trim().is_empty()) {
+        return Ok((absolute(expand_home(dir.trim()))?, RootSource::Config));
+    }
+
+    let home = home_council.context("HOME not set")?;
+    Ok((home.to_path_buf(), RootSource::Home))
+}
+
+/// Resolve the council root and make sure it exists.
+pub fn find_council_dir() -> Result<PathBuf> {
+    let (council, source) = resolve_council_root()?;
+    if !council.exists() {
+        fs::create_dir_all(&council)
+            .context(format!("Failed to create council directory: {}", council.display()))?;
+    }
+    log_debug!("council root {} (from {})", council.display(), source);
+    Ok(council)
+}
+
+/// Walk up from `start` looking for a `.council` directory. The home-level
+/// `~/.council` is not a project directory and is skipped so it does not
+/// shadow the config file setting.
+fn discover_project_council(start: &Path, home_council: Option<&Path>) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(".council"))
+        .find(|candidate| candidate.is_dir() && Some(candidate.as_path()) != home_council)
+}
+
+fn absolute(path: PathBuf) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Ok(env::current_dir()?.join(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mcp-council-root-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(council_dir: Option<&str>) -> Config {
+        Config {
+            council_dir: council_dir.map(|d| d.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn the_env_override_wins() {
+        crate::conformance::council_root();
+        let expected = PathBuf::from(env::var(COUNCIL_DIR_ENV).unwrap());
+        assert_eq!(resolve_council_root().unwrap(), (expected, RootSource::Env));
+    }
+
+    #[test]
+    fn project_local_is_opt_in() {
+        let enabled = Config {
+            project_local: true,
+            ..Default::default()
+        };
+        assert!(project_local_enabled(&enabled, None));
+        assert!(!project_local_enabled(&Config::default(), None));
+        for value in ["1", "true", "TRUE"] {
+            assert!(project_local_enabled(&Config::default(), Some(value)), "{}", value);
+        }
+        for value in ["0", "false", "yes", ""] {
+            assert!(!project_local_enabled(&Config::default(), Some(value)), "{}", value);
+        }
+    }
+
+    #[test]
+    fn discovery_finds_the_nearest_council() {
+        let root = scratch("nearest");
+        let deep = root.join("repo/src/module");
+        fs::create_dir_all(&deep).unwrap();
+        assert_eq!(discover_project_council(&deep, None), None);
+
+        fs::create_dir_all(root.join(".council")).unwrap();
+        assert_eq!(discover_project_council(&deep, None), Some(root.join(".council")));
+
+        fs::create_dir_all(root.join("repo/.council")).unwrap();
+        assert_eq!(discover_project_council(&deep, None), Some(root.join("repo/.council")));
+
+        // A file named .council is not a council
+        fs::write(root.join("repo/src/.council"), "").unwrap();
+        assert_eq!(discover_project_council(&deep, None), Some(root.join("repo/.council")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn discovery_skips_the_home_council() {
+        // `root` plays $HOME, so root/.council is ~/.council
+        let root = scratch("home");
+        let home_council = root.join(".council");
+        let project = root.join("work/project");
+        fs::create_dir_all(&home_council).unwrap();
+        fs::create_dir_all(&project).unwrap();
+        assert_eq!(discover_project_council(&project, Some(&home_council)), None);
+
+        fs::create_dir_all(project.join(".council")).unwrap();
+        assert_eq!(
+            discover_project_council(&project, Some(&home_council)),
+            Some(project.join(".council"))
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn resolution_follows_the_documented_order() {
+        let root = scratch("order");
+        let project = root.join("repo");
+        fs::create_dir_all(project.join(".council")).unwrap();
+        let home_council = root.join("home/.council");
+        let configured = root.join("configured");
+        let configured_str = configured.to_string_lossy().to_string();
+
+        // Project-local before council_dir
+        let with_dir = config(Some(&configured_str));
+        assert_eq!(
+            resolve_configured(&with_dir, Some(&project), Some(&home_council)).unwrap(),
+            (project.join(".council"), RootSource::ProjectLocal)
+        );
+        // Disabled, or enabled with no .council found: council_dir
+        assert_eq!(
+            resolve_configured(&with_dir, None, Some(&home_council)).unwrap(),
+            (configured.clone(), RootSource::Config)
+        );
+        assert_eq!(
+            resolve_configured(&with_dir, Some(&root), Some(&home_council)).unwrap(),
+            (configured, RootSource::Config)
+        );
+        // A relative council_dir is taken from the working directory
+        let (relative, _) = resolve_configured(&config(Some("notes/council")), None, None).unwrap();
+        assert!(relative.is_absolute() && relative.ends_with("notes/council"), "{}", relative.display());
+
+        // Nothing configured: ~/.council
+        for unset in [config(None), config(Some("  "))] {
+            assert_eq!(
+                resolve_configured(&unset, None, Some(&home_council)).unwrap(),
+                (home_council.clone(), RootSource::Home)
+            );
+        }
+        let error = resolve_configured(&config(None), None, None).unwrap_err();
+        assert_eq!(error.to_string(), "HOME not set");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
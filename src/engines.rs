@@ -0,0 +1,481 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// How long an engine may run before it is killed.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+/// Stdout kept from an engine; anything past this is read and dropped.
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// How the prompt reaches the engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptVia {
+    /// Written to the process's stdin
+    #[default]
+    Stdin,
+    /// Substituted for `{prompt}` in `args`, or appended as the last argument
+    Arg,
+}
+
+/// How the answer is taken out of what the engine prints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// All of stdout, trimmed
+    #[default]
+    Raw,
+    /// Stdout parsed as JSON, then a dotted path such as `result` or
+    /// `choices.0.message.content`
+    Json { path: String },
+    /// A capture group (default 1, or the whole match when the pattern has
+    /// no groups) of the first match
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        group: Option<usize>,
+    },
+}
+
+/// A model served behind an OpenAI-compatible `/chat/completions` endpoint
+/// (vLLM, llama.cpp, a hosted API) instead of a CLI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiConfig {
+    /// Up to and including the version, e.g. `http://localhost:8000/v1`
+    pub base_url: String,
+    /// Sent as `model` in the request
+    pub model: String,
+    /// Environment variable holding the API key; no `Authorization` header
+    /// is sent without one
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+}
+
+/// Token counts reported by an HTTP engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
+/// When a failed call is tried again. With no exit codes and no stderr
+/// patterns, every non-zero exit is retryable, and for HTTP engines only
+/// 408, 429 and 5xx statuses are. Once either list is set, the status code
+/// stands in for the exit code and the response body for stderr.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Calls made in total, including the first
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the second attempt; doubled for each one after that
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    #[serde(default)]
+    pub retry_exit_codes: Vec<i32>,
+    /// Regexes matched against the stderr tail
+    #[serde(default)]
+    pub retry_stderr: Vec<String>,
+    #[serde(default = "default_retry_on_timeout")]
+    pub retry_on_timeout: bool,
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+fn default_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_retry_on_timeout() -> bool {
+    true
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            retry_exit_codes: Vec::new(),
+            retry_stderr: Vec::new(),
+            retry_on_timeout: default_retry_on_timeout(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a non-zero exit with this code and stderr is worth retrying.
+    pub fn retries_exit(&self, code: Option<i32>, stderr: &str) -> bool {
+        if self.retry_exit_codes.is_empty() && self.retry_stderr.is_empty() {
+            return true;
+        }
+        code.is_some_and(|code| self.retry_exit_codes.contains(&code))
+            || self.retry_stderr.iter().any(|pattern| match Regex::new(pattern) {
+                Ok(regex) => regex.is_match(stderr),
+                Err(e) => {
+                    log_warn!("Ignoring invalid retry_stderr pattern {:?}: {}", pattern, e);
+                    false
+                }
+            })
+    }
+
+    /// Whether an HTTP error status with this body is worth retrying. Other
+    /// 4xx statuses (a bad key, an unknown model) fail the same way again.
+    pub fn retries_status(&self, status: u16, body: &str) -> bool {
+        if self.retry_exit_codes.is_empty() && self.retry_stderr.is_empty() {
+            return matches!(status, 408 | 429 | 500..=599);
+        }
+        self.retries_exit(Some(i32::from(status)), body)
+    }
+
+    /// Wait before attempt `attempt` (2 for the first retry).
+    pub fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let doublings = attempt.saturating_sub(2).min(16);
+        std::time::Duration::from_millis(self.backoff_ms.saturating_mul(1 << doublings))
+    }
+}
+
+/// One model CLI or HTTP endpoint, as configured under `engines` in the
+/// config file:
+///
+/// ```json
+/// "claude": {
+///   "command": "claude",
+///   "args": ["-p", "{prompt}", "--output-format", "json"],
+///   "prompt_via": "arg",
+///   "env": { "CLAUDE_CODE_MAX_OUTPUT_TOKENS": "8192" },
+///   "working_dir": "~/scratch",
+///   "output": { "type": "json", "path": "result" },
+///   "timeout_secs": 300,
+///   "max_output_bytes": 1048576,
+///   "retry": { "max_attempts": 3, "backoff_ms": 2000, "retry_exit_codes": [75] },
+///   "fallback": ["gemini"]
+/// }
+/// ```
+///
+/// An engine with `openai` set is called over HTTP; `command`, `args`,
+/// `prompt_via`, `env`, `working_dir` and `output` do not apply to it:
+///
+/// ```json
+/// "llama": {
+///   "openai": { "base_url": "http://localhost:8000/v1", "model": "llama-3-70b" },
+///   "timeout_secs": 300
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Binary name looked up in `PATH`, or a path to it
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub prompt_via: PromptVia,
+    /// Extra environment variables for the process
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Directory to run in; `~` expands to the home directory
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub output: OutputFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai: Option<OpenAiConfig>,
+    /// Seconds before the process group is killed, or the HTTP request
+    /// abandoned
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Cap on captured stdout or HTTP response body
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Engines tried in order when this one still fails after its retries
+    #[serde(default)]
+    pub fallback: Vec<String>,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+fn default_max_output_bytes() -> usize {
+    DEFAULT_MAX_OUTPUT_BYTES
+}
+
+impl EngineConfig {
+    /// An engine that reads the prompt on stdin and prints the answer.
+    fn stdin(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            args: Vec::new(),
+            prompt_via: PromptVia::Stdin,
+            env: BTreeMap::new(),
+            working_dir: None,
+            output: OutputFormat::Raw,
+            openai: None,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            retry: RetryPolicy::default(),
+            fallback: Vec::new(),
+        }
+    }
+
+    /// The argument list with `{prompt}` filled in for `prompt_via: arg`.
+    pub fn command_args(&self, prompt: &str) -> Vec<String> {
+        if self.prompt_via == PromptVia::Stdin {
+            return self.args.clone();
+        }
+        let mut substituted = false;
+        let mut args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| {
+                if arg.contains("{prompt}") {
+                    substituted = true;
+                    arg.replace("{prompt}", prompt)
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect();
+        if !substituted {
+            args.push(prompt.to_string());
+        }
+        args
+    }
+
+    /// Take the answer out of the engine's stdout.
+    pub fn extract(&self, stdout: &str) -> Result<String> {
+        let answer = match &self.output {
+            OutputFormat::Raw => stdout.to_string(),
+            OutputFormat::Json { path } => {
+                let parsed: Value = serde_json::from_str(stdout.trim())
+                    .context(format!("{} did not print valid JSON", self.command))?;
+                let found = json_path(&parsed, path)
+                    .context(format!("No value at '{}' in {} output", path, self.command))?;
+                match found {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                }
+            }
+            OutputFormat::Regex { pattern, group } => {
+                let regex = Regex::new(pattern)
+                    .context(format!("Invalid output pattern for {}: {}", self.command, pattern))?;
+                let captures = regex
+                    .captures(stdout)
+                    .context(format!("Output pattern did not match {} output", self.command))?;
+                let group = group.unwrap_or(if captures.len() > 1 { 1 } else { 0 });
+                captures
+                    .get(group)
+                    .context(format!("Output pattern has no group {}", group))?
+                    .as_str()
+                    .to_string()
+            }
+        };
+        let answer = answer.trim();
+        if answer.is_empty() {
+            anyhow::bail!("{} produced no answer", self.command);
+        }
+        Ok(answer.to_string())
+    }
+}
+
+/// Follow a dotted path; numeric segments index arrays.
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => current.get(segment),
+        })
+}
+
+/// The engines `run_llm` knows: the built-in CLIs, overridden or extended
+/// by `engines` in the config file.
+#[derive(Debug, Clone)]
+pub struct EngineRegistry {
+    engines: BTreeMap<String, EngineConfig>,
+}
+
+impl EngineRegistry {
+    pub fn builtin() -> Self {
+        let engines = [
+            ("claude", "claude"),
+            ("gemini", "gemini-cli"),
+            ("cursor-agent", "cursor-agent"),
+            ("codex", "codex-cli"),
+        ]
+        .into_iter()
+        .map(|(name, command)| (name.to_string(), EngineConfig::stdin(command)))
+        .collect();
+        Self { engines }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        let mut registry = Self::builtin();
+        registry
+            .engines
+            .extend(config.engines.iter().map(|(name, engine)| (name.clone(), engine.clone())));
+        registry
+    }
+
+    pub fn load() -> Result<Self> {
+        Ok(Self::from_config(&Config::load()?))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&EngineConfig> {
+        self.engines.get(name).with_context(|| {
+            format!(
+                "Unknown engine: {}. Configured engines: {}",
+                name,
+                self.names().join(", ")
+            )
+        })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.engines.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn engine(config: Value) -> EngineConfig {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn prompts_are_substituted_or_appended() {
+        let substituted = engine(json!({
+            "command": "llm",
+            "args": ["-p", "{prompt}", "--note={prompt}!"],
+            "prompt_via": "arg"
+        }));
+        assert_eq!(substituted.command_args("hi"), ["-p", "hi", "--note=hi!"]);
+
+        let appended = engine(json!({ "command": "llm", "args": ["--quiet"], "prompt_via": "arg" }));
+        assert_eq!(appended.command_args("hi"), ["--quiet", "hi"]);
+
+        // On stdin the arguments stay as configured
+        let stdin = engine(json!({ "command": "llm", "args": ["{prompt}"] }));
+        assert_eq!(stdin.command_args("hi"), ["{prompt}"]);
+    }
+
+    #[test]
+    fn json_paths_index_objects_and_arrays() {
+        let output = engine(json!({
+            "command": "llm",
+            "output": { "type": "json", "path": "choices.0.message.content" }
+        }));
+        let stdout = r#"{"choices":[{"message":{"content":"  four  "}}]}"#;
+        assert_eq!(output.extract(stdout).unwrap(), "four");
+
+        let value = json!({ "a": [{ "b": 2 }, { "b": 3 }] });
+        assert_eq!(json_path(&value, "a.1.b"), Some(&json!(3)));
+        assert_eq!(json_path(&value, ""), Some(&value));
+        assert_eq!(json_path(&value, "a.2.b"), None);
+        assert_eq!(json_path(&value, "a.x"), None);
+
+        // Non-string values are returned as JSON text
+        let number = engine(json!({ "command": "llm", "output": { "type": "json", "path": "n" } }));
+        assert_eq!(number.extract(r#"{"n": 4}"#).unwrap(), "4");
+
+        let error = output.extract(r#"{"choices":[]}"#).unwrap_err().to_string();
+        assert_eq!(error, "No value at 'choices.0.message.content' in llm output");
+        assert!(output.extract("not json").is_err());
+    }
+
+    #[test]
+    fn regexes_take_the_first_group_or_the_whole_match() {
+        let grouped = engine(json!({
+            "command": "llm",
+            "output": { "type": "regex", "pattern": "ANSWER: (.*)" }
+        }));
+        assert_eq!(grouped.extract("thinking...\nANSWER: 42\n").unwrap(), "42");
+
+        let whole = engine(json!({
+            "command": "llm",
+            "output": { "type": "regex", "pattern": "[0-9]+" }
+        }));
+        assert_eq!(whole.extract("it is 42, not 7").unwrap(), "42");
+
+        let explicit = engine(json!({
+            "command": "llm",
+            "output": { "type": "regex", "pattern": "(\\w+)=(\\w+)", "group": 2 }
+        }));
+        assert_eq!(explicit.extract("key=value").unwrap(), "value");
+
+        let error = grouped.extract("no answer").unwrap_err().to_string();
+        assert_eq!(error, "Output pattern did not match llm output");
+    }
+
+    #[test]
+    fn empty_output_is_an_error() {
+        let raw = engine(json!({ "command": "llm" }));
+        assert_eq!(raw.extract("  answer \n").unwrap(), "answer");
+        assert_eq!(raw.extract(" \n\t").unwrap_err().to_string(), "llm produced no answer");
+
+        let json_output = engine(json!({ "command": "llm", "output": { "type": "json", "path": "result" } }));
+        assert_eq!(
+            json_output.extract(r#"{"result": ""}"#).unwrap_err().to_string(),
+            "llm produced no answer"
+        );
+    }
+
+    #[test]
+    fn default_retries_skip_http_client_errors() {
+        let default = RetryPolicy::default();
+        assert!(default.retries_exit(Some(1), ""));
+        for status in [408, 429, 500, 502, 503, 599] {
+            assert!(default.retries_status(status, ""), "{}", status);
+        }
+        for status in [400, 401, 403, 404, 422] {
+            assert!(!default.retries_status(status, ""), "{}", status);
+        }
+
+        let configured: RetryPolicy = serde_json::from_value(json!({ "retry_exit_codes": [401] })).unwrap();
+        assert!(configured.retries_status(401, ""));
+        assert!(!configured.retries_status(503, ""));
+        let by_body: RetryPolicy = serde_json::from_value(json!({ "retry_stderr": ["overloaded"] })).unwrap();
+        assert!(by_body.retries_status(400, "model overloaded"));
+        assert!(!by_body.retries_status(400, "bad request"));
+    }
+
+    #[test]
+    fn config_engines_override_builtins() {
+        let config: Config = serde_json::from_value(json!({
+            "engines": {
+                "claude": { "command": "my-claude", "args": ["-p"], "prompt_via": "arg" },
+                "local": { "command": "llama" }
+            }
+        }))
+        .unwrap();
+        let registry = EngineRegistry::from_config(&config);
+
+        let claude = registry.get("claude").unwrap();
+        assert_eq!(claude.command, "my-claude");
+        assert_eq!(claude.command_args("q"), ["-p", "q"]);
+        assert_eq!(registry.get("gemini").unwrap().command, "gemini-cli");
+        assert_eq!(registry.get("local").unwrap().timeout_secs, DEFAULT_TIMEOUT_SECS);
+        assert_eq!(registry.names(), ["claude", "codex", "cursor-agent", "gemini", "local"]);
+
+        let error = registry.get("missing").unwrap_err().to_string();
+        assert!(error.starts_with("Unknown engine: missing."), "{}", error);
+    }
+}
@@ -0,0 +1,653 @@
+use crate::client::Outgoing;
+use crate::mcp::{check_envelope, usable_id, McpServer, SUPPORTED_PROTOCOL_VERSIONS};
+use anyhow::{bail, Context, Result};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
+
+/// Path of the single MCP endpoint.
+pub const ENDPOINT: &str = "/mcp";
+
+/// Header carrying the session id assigned on `initialize`.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Header carrying the negotiated protocol revision on requests after
+/// `initialize`.
+const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// Sessions with no request, open stream or delivered message for this long
+/// are ended, since most clients never send DELETE.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often idle sessions are looked for.
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A request turned away before it reaches the server.
+type Rejection = (StatusCode, &'static str);
+
+/// The response channel of one POST. JSON-mode POSTs only take responses;
+/// server-initiated messages need an SSE stream.
+struct RequestStream {
+    tx: mpsc::UnboundedSender<String>,
+    responses_only: bool,
+}
+
+impl RequestStream {
+    /// The channel for server-initiated messages, if this POST takes them.
+    fn events(&self) -> Option<&mpsc::UnboundedSender<String>> {
+        (!self.responses_only).then_some(&self.tx)
+    }
+}
+
+/// Where messages for one session can be delivered.
+#[derive(Default)]
+struct Streams {
+    /// Open POSTs, keyed by the ids of the requests they carry
+    requests: HashMap<String, RequestStream>,
+    /// The stream opened with GET, if the client keeps one
+    standalone: Option<mpsc::UnboundedSender<String>>,
+    /// Server-initiated messages sent while no stream was open
+    backlog: Vec<String>,
+}
+
+impl Streams {
+    /// A response goes to the POST that carried its request, and ends that
+    /// request's share of the stream. Server-initiated messages prefer the
+    /// stream of the request they were sent for, then the GET stream, then
+    /// any open SSE POST; they are kept for later otherwise.
+    fn deliver(&mut self, outgoing: Outgoing) {
+        if outgoing.completes {
+            if let Some(stream) = outgoing.origin.as_ref().and_then(|o| self.requests.remove(o)) {
+                if let Some(message) = outgoing.message {
+                    let _ = stream.tx.send(message);
+                }
+                return;
+            }
+        }
+        let Some(mut message) = outgoing.message else {
+            return;
+        };
+
+        let origin = outgoing
+            .origin
+            .as_ref()
+            .and_then(|o| self.requests.get(o))
+            .and_then(RequestStream::events);
+        for target in origin
+            .into_iter()
+            .chain(self.standalone.as_ref())
+            .chain(self.requests.values().filter_map(RequestStream::events))
+        {
+            match target.send(message) {
+                Ok(()) => return,
+                Err(e) => message = e.0,
+            }
+        }
+        self.backlog.push(message);
+    }
+
+    fn flush_backlog(&mut self, target: &mpsc::UnboundedSender<String>) {
+        for message in self.backlog.drain(..) {
+            let _ = target.send(message);
+        }
+    }
+
+    /// Nothing is in flight and no GET stream is listening.
+    fn is_idle(&self) -> bool {
+        self.requests.is_empty() && self.standalone.as_ref().map_or(true, |tx| tx.is_closed())
+    }
+}
+
+/// One connected client: its own `McpServer` (capabilities, in-flight
+/// requests, pending sampling calls) and the streams it is listening on.
+struct Session {
+    server: Arc<McpServer>,
+    streams: Mutex<Streams>,
+    /// Last request from the client or message delivered to it
+    last_active: Mutex<Instant>,
+}
+
+impl Session {
+    fn touch(&self) {
+        *self.last_active.lock().unwrap() = Instant::now();
+    }
+
+    /// In-flight sampling calls fail and its streams close.
+    fn end(&self) {
+        self.server.shutdown();
+        self.streams.lock().unwrap().standalone = None;
+    }
+}
+
+struct HttpState {
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+    idle_timeout: Duration,
+}
+
+impl HttpState {
+    fn new(idle_timeout: Duration) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            idle_timeout,
+        }
+    }
+
+    fn create_session(&self) -> Result<(String, Arc<Session>)> {
+        let server = Arc::new(McpServer::new());
+        let mut outgoing = server.take_outgoing()?;
+        let session = Arc::new(Session {
+            server,
+            streams: Mutex::new(Streams::default()),
+            last_active: Mutex::new(Instant::now()),
+        });
+
+        // Ends when the session is deleted and its client connection closed
+        let pump = Arc::clone(&session);
+        tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                pump.touch();
+                pump.streams.lock().unwrap().deliver(message);
+            }
+        });
+
+        let id = uuid::Uuid::new_v4().to_string();
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::clone(&session));
+        log_info!("HTTP session {} created", id);
+        Ok((id, session))
+    }
+
+    fn session(&self, headers: &HeaderMap) -> Result<(String, Arc<Session>), Rejection> {
+        let id = session_id(headers)
+            .ok_or((StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"))?;
+        let session = self.sessions.lock().unwrap().get(id).cloned();
+        match session {
+            Some(session) => {
+                session.touch();
+                Ok((id.to_string(), session))
+            }
+            None => Err((StatusCode::NOT_FOUND, "Session not found")),
+        }
+    }
+
+    /// End sessions that have been idle for longer than `idle_timeout`, as
+    /// if the client had sent DELETE. Returns how many were ended.
+    fn expire_idle(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        let expired: Vec<String> = sessions
+            .iter()
+            .filter(|(_, session)| {
+                session.last_active.lock().unwrap().elapsed() >= self.idle_timeout
+                    && session.streams.lock().unwrap().is_idle()
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            if let Some(session) = sessions.remove(id) {
+                session.end();
+                log_info!("HTTP session {} expired after {}s idle", id, self.idle_timeout.as_secs());
+            }
+        }
+        expired.len()
+    }
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok())
+}
+
+fn accepts(headers: &HeaderMap, mime: &str) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(mime) || accept.contains("*/*"))
+}
+
+/// Reject browser pages from other sites (DNS rebinding). Clients that do
+/// not send `Origin`, as MCP clients usually don't, are allowed.
+fn check_origin(headers: &HeaderMap) -> Result<(), Rejection> {
+    let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return Ok(());
+    };
+    let host = origin
+        .split("://")
+        .nth(1)
+        .unwrap_or(origin)
+        .trim_start_matches('[')
+        .split([']', ':'])
+        .next()
+        .unwrap_or_default();
+    if matches!(host, "localhost" | "127.0.0.1" | "::1") {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Origin not allowed"))
+    }
+}
+
+/// Clients on 2025-06-18 and later repeat the negotiated revision on every
+/// request; older ones send nothing, which is accepted.
+fn check_protocol_version(headers: &HeaderMap) -> Result<(), Rejection> {
+    match headers.get(PROTOCOL_VERSION_HEADER).map(|v| v.to_str()) {
+        None => Ok(()),
+        Some(Ok(version)) if SUPPORTED_PROTOCOL_VERSIONS.contains(&version) => Ok(()),
+        Some(_) => Err((StatusCode::BAD_REQUEST, "Unsupported MCP-Protocol-Version")),
+    }
+}
+
+fn with_session(mut response: Response, id: &str) -> Response {
+    if let Ok(value) = HeaderValue::from_str(id) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    response
+}
+
+fn sse_stream(rx: mpsc::UnboundedReceiver<String>) -> Response {
+    let events = UnboundedReceiverStream::new(rx)
+        .map(|message| Ok::<_, Infallible>(Event::default().event("message").data(message)));
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// A JSON-RPC error for a body that cannot be handled at all.
+fn rpc_error(code: i32, message: String) -> Response {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": null,
+        "error": { "code": code, "message": message }
+    });
+    (StatusCode::BAD_REQUEST, axum::Json(body)).into_response()
+}
+
+/// Client messages. Requests are answered on an SSE stream that also
+/// carries the progress notifications and sampling requests they trigger,
+/// or as plain JSON when the client does not accept `text/event-stream`.
+async fn handle_post(
+    State(state): State<Arc<HttpState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if let Err(rejection) = check_origin(&headers) {
+        return rejection.into_response();
+    }
+    let parsed: Value = match serde_json::from_str(&body) {
+        Ok(parsed) => parsed,
+        Err(e) => return rpc_error(-32700, format!("Parse error: {}", e)),
+    };
+    let batch = parsed.is_array();
+    let messages = match parsed {
+        Value::Array(items) if items.is_empty() => {
+            return rpc_error(-32600, "Invalid Request: empty batch".to_string())
+        }
+        Value::Array(items) => items,
+        other => vec![other],
+    };
+
+    let is_initialize = messages.iter().any(|m| m["method"] == "initialize");
+    let (id, session) = match session_id(&headers) {
+        None if is_initialize => match state.create_session() {
+            Ok(created) => created,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        _ => match check_protocol_version(&headers).and_then(|()| state.session(&headers)) {
+            Ok(found) => found,
+            Err(rejection) => return rejection.into_response(),
+        },
+    };
+
+    // Invalid requests are answered right away rather than on a stream
+    let request_keys: Vec<String> = messages
+        .iter()
+        .filter(|m| check_envelope(m).is_ok())
+        .filter_map(usable_id)
+        .map(|id| id.to_string())
+        .collect();
+
+    if request_keys.is_empty() {
+        let mut tasks = JoinSet::new();
+        let rejected: Vec<String> = messages
+            .into_iter()
+            .filter_map(|message| session.server.accept_message(&mut tasks, message, false))
+            .collect();
+        tokio::spawn(async move { while tasks.join_next().await.is_some() {} });
+        return with_session(json_responses(batch, rejected), &id);
+    }
+
+    // Register the response stream before the handlers start, so nothing
+    // they send ends up on another stream. It closes once every request in
+    // this POST has completed.
+    let sse = accepts(&headers, "text/event-stream");
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    {
+        let mut streams = session.streams.lock().unwrap();
+        for key in &request_keys {
+            let stream = RequestStream {
+                tx: tx.clone(),
+                responses_only: !sse,
+            };
+            streams.requests.insert(key.clone(), stream);
+        }
+        if sse {
+            streams.flush_backlog(&tx);
+        }
+    }
+
+    // A JSON response carries nothing but responses, so a sampling request
+    // would never reach the client; tools see sampling as unavailable
+    let mut tasks = JoinSet::new();
+    for message in messages {
+        if let Some(rejected) = session.server.accept_message(&mut tasks, message, sse) {
+            let _ = tx.send(rejected);
+        }
+    }
+    drop(tx);
+    // Dropping the set would abort the handlers
+    tokio::spawn(async move { while tasks.join_next().await.is_some() {} });
+
+    if sse {
+        return with_session(sse_stream(rx), &id);
+    }
+
+    let mut responses = Vec::new();
+    while let Some(response) = rx.recv().await {
+        responses.push(response);
+    }
+    with_session(json_responses(batch, responses), &id)
+}
+
+/// Responses as one JSON body: an array for a batch, a single object
+/// otherwise, and `202` when there is nothing to say.
+fn json_responses(batch: bool, mut responses: Vec<String>) -> Response {
+    let body = match (batch, responses.len()) {
+        (_, 0) => return StatusCode::ACCEPTED.into_response(),
+        (false, 1) => responses.remove(0),
+        _ => format!("[{}]", responses.join(",")),
+    };
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+/// A standalone SSE stream for server-initiated messages that are not tied
+/// to an open POST.
+async fn handle_get(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+    if let Err(rejection) = check_origin(&headers) {
+        return rejection.into_response();
+    }
+    if !accepts(&headers, "text/event-stream") {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    let (id, session) = match check_protocol_version(&headers).and_then(|()| state.session(&headers)) {
+        Ok(found) => found,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    {
+        let mut streams = session.streams.lock().unwrap();
+        streams.flush_backlog(&tx);
+        streams.standalone = Some(tx);
+    }
+    with_session(sse_stream(rx), &id)
+}
+
+/// End a session at the client's request.
+async fn handle_delete(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+    if let Err(rejection) = check_origin(&headers) {
+        return rejection.into_response();
+    }
+    let (id, session) = match state.session(&headers) {
+        Ok(found) => found,
+        Err(rejection) => return rejection.into_response(),
+    };
+    state.sessions.lock().unwrap().remove(&id);
+    session.end();
+    log_info!("HTTP session {} deleted", id);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+fn router(state: Arc<HttpState>) -> Router {
+    Router::new()
+        .route(ENDPOINT, post(handle_post).get(handle_get).delete(handle_delete))
+        .with_state(state)
+}
+
+/// Serve the MCP Streamable HTTP transport on `addr` until the process exits.
+///
+/// The endpoint has no authentication, so `addr` must be a loopback address
+/// unless `allow_remote` is set.
+pub async fn serve(addr: &str, allow_remote: bool) -> Result<()> {
+    let state = Arc::new(HttpState::new(SESSION_IDLE_TIMEOUT));
+    let sweeper = Arc::clone(&state);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SESSION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            sweeper.expire_idle();
+        }
+    });
+    let app = router(state);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .context(format!("Failed to bind {}", addr))?;
+    let local = listener.local_addr()?;
+    if !local.ip().is_loopback() {
+        if !allow_remote {
+            bail!(
+                "Refusing to serve on non-loopback address {}: the HTTP endpoint has no \
+                 authentication. Pass --allow-remote to bind it anyway.",
+                local
+            );
+        }
+        log_warn!(
+            "Serving on non-loopback address {} with no authentication; anyone who can reach it can use the council",
+            local
+        );
+    }
+    log_info!("mcp-council listening on http://{}{}", local, ENDPOINT);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCEPT_JSON: &str = "application/json";
+
+    /// Serve the MCP endpoint on a random local port.
+    async fn http_server(state: Arc<HttpState>) -> String {
+        crate::conformance::council_root();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await.unwrap() });
+        format!("http://{}{}", addr, ENDPOINT)
+    }
+
+    struct HttpClient {
+        http: reqwest::Client,
+        url: String,
+        session: Option<String>,
+    }
+
+    impl HttpClient {
+        fn new(url: String) -> Self {
+            let http = reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap();
+            Self { http, url, session: None }
+        }
+
+        fn headers(&self) -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, HeaderValue::from_static(ACCEPT_JSON));
+            if let Some(id) = &self.session {
+                headers.insert(SESSION_HEADER, HeaderValue::from_str(id).unwrap());
+            }
+            headers
+        }
+
+        async fn post(&self, message: Value) -> reqwest::Response {
+            self.http
+                .post(&self.url)
+                .headers(self.headers())
+                .json(&message)
+                .send()
+                .await
+                .unwrap()
+        }
+
+        async fn request(&self, id: i64, method: &str, params: Value) -> Value {
+            let response = self
+                .post(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["id"], id, "{}", body);
+            body
+        }
+
+        /// `initialize` in JSON response mode, keeping the assigned session id.
+        async fn initialize(&mut self, capabilities: Value) {
+            let response = self
+                .post(json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "method": "initialize",
+                    "params": { "protocolVersion": "2025-06-18", "capabilities": capabilities }
+                }))
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let id = response.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["result"]["protocolVersion"], "2025-06-18");
+            self.session = Some(id);
+
+            let response = self
+                .post(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+                .await;
+            assert_eq!(response.status(), StatusCode::ACCEPTED);
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions_run_from_initialize_to_delete() {
+        let state = Arc::new(HttpState::new(SESSION_IDLE_TIMEOUT));
+        let mut client = HttpClient::new(http_server(Arc::clone(&state)).await);
+
+        // Requests other than initialize need a session
+        let response = client.post(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        client.initialize(json!({})).await;
+        let tools = client.request(2, "tools/list", json!({})).await;
+        let names = tools["result"]["tools"].to_string();
+        assert!(names.contains("council.first_answer"), "{}", names);
+
+        let deleted = client
+            .http
+            .delete(&client.url)
+            .headers(client.headers())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+        assert!(state.sessions.lock().unwrap().is_empty());
+        let response = client.post(json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" })).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn foreign_origins_are_forbidden() {
+        let state = Arc::new(HttpState::new(SESSION_IDLE_TIMEOUT));
+        let client = HttpClient::new(http_server(Arc::clone(&state)).await);
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-06-18", "capabilities": {} }
+        });
+
+        let response = client
+            .http
+            .post(&client.url)
+            .headers(client.headers())
+            .header(header::ORIGIN, "https://evil.example")
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(state.sessions.lock().unwrap().is_empty());
+
+        let response = client
+            .http
+            .post(&client.url)
+            .headers(client.headers())
+            .header(header::ORIGIN, "http://localhost:5173")
+            .json(&initialize)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_expire() {
+        let state = Arc::new(HttpState::new(Duration::from_millis(200)));
+        let mut client = HttpClient::new(http_server(Arc::clone(&state)).await);
+        client.initialize(json!({})).await;
+
+        assert_eq!(state.expire_idle(), 0);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(state.expire_idle(), 1);
+        assert!(state.sessions.lock().unwrap().is_empty());
+
+        let response = client.post(json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" })).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn json_responses_report_sampling_as_unavailable() {
+        let state = Arc::new(HttpState::new(SESSION_IDLE_TIMEOUT));
+        let mut client = HttpClient::new(http_server(state).await);
+        client.initialize(json!({ "sampling": {} })).await;
+
+        let title = "http-json-sampling";
+        for (id, model) in [(1, "gpt"), (2, "gemini")] {
+            let arguments = json!({ "title": title, "model": model, "prompt": "q?", "content": model });
+            let saved = client
+                .request(id, "tools/call", json!({ "name": "council.first_answer", "arguments": arguments }))
+                .await;
+            assert_eq!(saved["result"]["structuredContent"]["success"], true, "{}", saved);
+        }
+
+        // With SSE this would ask the client's model; a JSON response cannot
+        // carry the request, so the prompt comes back instead
+        let arguments = json!({ "title": title, "model": "gemini", "self_model": "gemini" });
+        let review = client
+            .request(3, "tools/call", json!({ "name": "council.peer_review", "arguments": arguments }))
+            .await;
+        let result = &review["result"]["structuredContent"];
+        assert_eq!(result["action"], "perform_peer_review_and_save", "{}", review);
+        assert!(result.get("sampling_error").is_none(), "{}", review);
+    }
+
+    #[tokio::test]
+    async fn non_loopback_bind_needs_allow_remote() {
+        let err = serve("0.0.0.0:0", false).await.unwrap_err();
+        assert!(err.to_string().contains("--allow-remote"), "{}", err);
+    }
+}
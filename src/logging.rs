@@ -0,0 +1,290 @@
+use crate::client::ClientPeer;
+use crate::config::Config;
+use crate::council_dir::resolve_council_root;
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Minimum level written to stderr and the log file (`debug`, `info`, ...).
+pub const LOG_LEVEL_ENV: &str = "MCP_COUNCIL_LOG";
+
+/// Set to `1` to also write the log to `<council root>/logs/mcp-council.log`.
+pub const LOG_FILE_ENV: &str = "MCP_COUNCIL_LOG_FILE";
+
+/// Set to `1` to log request and response bodies unredacted.
+pub const LOG_PAYLOADS_ENV: &str = "MCP_COUNCIL_LOG_PAYLOADS";
+
+const LOG_FILE_NAME: &str = "mcp-council.log";
+
+/// The log file is rotated once it would grow past this size.
+const LOG_FILE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept next to the current one (`.1` is the newest).
+const LOG_FILES_KEPT: usize = 3;
+
+/// Fields whose string values are kept when a payload is redacted: protocol
+/// plumbing and identifiers, never prompts, answers or reviews.
+const PLAIN_FIELDS: &[&str] = &[
+    "jsonrpc",
+    "id",
+    "method",
+    "name",
+    "title",
+    "model",
+    "self_model",
+    "engine",
+    "uri",
+    "protocolVersion",
+    "progressToken",
+    "requestId",
+    "level",
+    "action",
+    "type",
+    "role",
+    "stopReason",
+];
+
+tokio::task_local! {
+    /// The connection whose request is being handled, so log records made
+    /// while handling it can be forwarded as `notifications/message`.
+    pub static CONNECTION: Arc<ClientPeer>;
+}
+
+/// Syslog severities, as used by MCP `logging/setLevel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl Level {
+    pub const ALL: [Level; 8] = [
+        Level::Debug,
+        Level::Info,
+        Level::Notice,
+        Level::Warning,
+        Level::Error,
+        Level::Critical,
+        Level::Alert,
+        Level::Emergency,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Notice => "notice",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Critical => "critical",
+            Level::Alert => "alert",
+            Level::Emergency => "emergency",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        // `warn` is what people type in MCP_COUNCIL_LOG
+        let s = if s == "warn" { "warning".to_string() } else { s };
+        Level::ALL
+            .into_iter()
+            .find(|level| level.as_str() == s)
+            .ok_or_else(|| format!("Unknown log level: {}", s))
+    }
+}
+
+/// A size-rotated log file.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > LOG_FILE_MAX_BYTES {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// `mcp-council.log` becomes `.1`, `.1` becomes `.2`, and the oldest is
+    /// dropped.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        let _ = fs::remove_file(numbered(LOG_FILES_KEPT));
+        for n in (1..LOG_FILES_KEPT).rev() {
+            let _ = fs::rename(numbered(n), numbered(n + 1));
+        }
+        fs::rename(&self.path, numbered(1))?;
+        *self = Self::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+/// Process-wide sinks: stderr and the optional log file. Forwarding to a
+/// client is per connection, at the level that client asked for.
+struct Logger {
+    level: Level,
+    redact_payloads: bool,
+    file: Option<Mutex<LogFile>>,
+}
+
+fn env_flag(name: &str) -> Option<bool> {
+    env::var(name)
+        .ok()
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+impl Logger {
+    fn from_env() -> Self {
+        // Logging is set up before anything else runs, so a broken config
+        // file only means default logging; the tools report it themselves
+        let config = Config::load().unwrap_or_default();
+        let level = env::var(LOG_LEVEL_ENV)
+            .ok()
+            .or(config.log_level)
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(Level::Info);
+        let redact_payloads = !env_flag(LOG_PAYLOADS_ENV).unwrap_or(config.log_payloads);
+
+        let file = env_flag(LOG_FILE_ENV)
+            .unwrap_or(config.log_file)
+            .then(|| resolve_council_root().ok())
+            .flatten()
+            .and_then(|(root, _)| {
+                let path = root.join("logs").join(LOG_FILE_NAME);
+                match LogFile::open(path.clone()) {
+                    Ok(file) => Some(Mutex::new(file)),
+                    Err(e) => {
+                        eprintln!("WARNING: Cannot open log file {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            });
+
+        Self {
+            level,
+            redact_payloads,
+            file,
+        }
+    }
+}
+
+fn logger() -> &'static Logger {
+    static LOGGER: OnceLock<Logger> = OnceLock::new();
+    LOGGER.get_or_init(Logger::from_env)
+}
+
+/// Record one message. Use the `log_*!` macros rather than calling this.
+pub fn log(level: Level, target: &str, message: String) {
+    let logger = logger();
+    // Module path without the crate name, e.g. `tools::tally`
+    let target = target.split_once("::").map_or(target, |(_, rest)| rest);
+
+    if level >= logger.level {
+        eprintln!("{}: {}", level.as_str().to_ascii_uppercase(), message);
+        if let Some(file) = &logger.file {
+            let line = format!("{} {:<9} {}: {}", Utc::now().to_rfc3339(), level, target, message);
+            if let Err(e) = file.lock().unwrap().write_line(&line) {
+                eprintln!("WARNING: Failed to write log file: {}", e);
+            }
+        }
+    }
+
+    let _ = CONNECTION.try_with(|client| client.log_message(level, target, &message));
+}
+
+/// A request or response body for a log line. Unless payload logging is on,
+/// string values are replaced with their length, except for identifiers
+/// such as `method`, `title` and `model`.
+pub fn payload(message: &Value) -> String {
+    if logger().redact_payloads {
+        redact(message).to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+/// Like `payload`, for a message that is still a JSON string.
+pub fn payload_str(message: &str) -> String {
+    match serde_json::from_str::<Value>(message) {
+        Ok(value) => payload(&value),
+        Err(_) if logger().redact_payloads => format!("<{} bytes, not JSON>", message.len()),
+        Err(_) => message.to_string(),
+    }
+}
+
+fn redact(value: &Value) -> Value {
+    match value {
+        Value::String(s) => json!(format!("<redacted {} chars>", s.chars().count())),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        Value::Object(object) => {
+            let redacted: Map<String, Value> = object
+                .iter()
+                .map(|(key, value)| {
+                    let kept = value.is_string() && PLAIN_FIELDS.contains(&key.as_str());
+                    (key.clone(), if kept { value.clone() } else { redact(value) })
+                })
+                .collect();
+            Value::Object(redacted)
+        }
+        other => other.clone(),
+    }
+}
+
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        $crate::logging::log($level, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log_at!($crate::logging::Level::Debug, $($arg)*) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => { log_at!($crate::logging::Level::Info, $($arg)*) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => { log_at!($crate::logging::Level::Warning, $($arg)*) };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => { log_at!($crate::logging::Level::Error, $($arg)*) };
+}
@@ -1,6 +1,26 @@
+#[macro_use]
+mod logging;
 mod mcp;
 mod tools;
 mod cli_runner;
+mod engines;
+mod aggregate;
+mod client;
+mod commands;
+#[cfg(test)]
+mod conformance;
+mod config;
+mod council_dir;
+mod http;
+mod manifest;
+mod openai_runner;
+mod orchestrate;
+mod progress;
+mod prompts;
+mod ranking;
+mod resources;
+mod schema;
+mod slug;
 
 use anyhow::Result;
 use mcp::McpServer;
@@ -9,14 +29,10 @@ use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::path::PathBuf;
+use std::sync::Arc;
 
-// Embed command files at compile time
-const CMD_FINALIZE: &str = include_str!("../commands/cc/finalize.md");
-const CMD_FIRST_ANSWER: &str = include_str!("../commands/cc/first_answer.md");
-const CMD_PEER_REVIEW: &str = include_str!("../commands/cc/peer_review.md");
-const CMD_SAVE_REVIEW: &str = include_str!("../commands/cc/save_review.md");
-const CMD_SAVE_SUMMARY: &str = include_str!("../commands/cc/save_summary.md");
-const CMD_SUMMARIZE: &str = include_str!("../commands/cc/summarize.md");
+/// Address used by `--http` when none is given.
+const DEFAULT_HTTP_ADDR: &str = "127.0.0.1:8765";
 
 fn print_help() {
     eprintln!("mcp-council - MCP server for multi-LLM peer review workflow");
@@ -26,8 +42,28 @@ fn print_help() {
     eprintln!("  mcp-council --init         Install to both Cursor and Claude Code (interactive)");
     eprintln!("  mcp-council --init-cursor  Install to ~/.cursor/commands/<folder>/");
     eprintln!("  mcp-council --init-claude  Install to ~/.claude/commands/<folder>/");
+    eprintln!("  mcp-council --http [addr]  Serve MCP over Streamable HTTP at http://<addr>/mcp");
+    eprintln!("                             (default {}); a non-loopback addr also needs", DEFAULT_HTTP_ADDR);
+    eprintln!("                             --allow-remote, as the endpoint has no authentication");
+    eprintln!("  {}", orchestrate::RUN_USAGE);
+    eprintln!("                             Run all three stages through the model CLIs and");
+    eprintln!("                             print the final answer (chair defaults to the first model)");
     eprintln!("  mcp-council --help         Show this help message");
     eprintln!();
+    eprintln!("COUNCIL DIRECTORY (first match wins):");
+    eprintln!("  1. $MCP_COUNCIL_DIR");
+    eprintln!("  2. .council in the working directory or a parent, when project_local is");
+    eprintln!("     enabled in the config file or MCP_COUNCIL_PROJECT_LOCAL=1");
+    eprintln!("  3. council_dir in the config file");
+    eprintln!("  4. ~/.council");
+    eprintln!("  A project-local .council is checked before council_dir, so once enabled");
+    eprintln!("  a repository's own councils win over the user-wide default.");
+    eprintln!();
+    eprintln!("CONFIG FILE:");
+    eprintln!("  $MCP_COUNCIL_CONFIG, else $XDG_CONFIG_HOME/mcp-council/config.json,");
+    eprintln!("  else ~/.config/mcp-council/config.json");
+    eprintln!("  {{ \"council_dir\": \"~/notes/council\", \"project_local\": true }}");
+    eprintln!();
 }
 
 fn prompt_subfolder() -> String {
@@ -48,29 +84,18 @@ fn prompt_subfolder() -> String {
 fn install_commands_to(cmd_dir: PathBuf) -> Result<()> {
     fs::create_dir_all(&cmd_dir)?;
 
-    let commands = [
-        ("finalize.md", CMD_FINALIZE),
-        ("first_answer.md", CMD_FIRST_ANSWER),
-        ("peer_review.md", CMD_PEER_REVIEW),
-        ("save_review.md", CMD_SAVE_REVIEW),
-        ("save_summary.md", CMD_SAVE_SUMMARY),
-        ("summarize.md", CMD_SUMMARIZE),
-    ];
-
-    for (name, content) in commands {
+    for (name, content) in commands::COMMANDS {
         let path = cmd_dir.join(name);
         fs::write(&path, content)?;
         eprintln!("  Installed: {}", path.display());
     }
 
     // Ensure council directory exists
-    let home = env::var("HOME").expect("HOME environment variable not set");
-    let council_dir = PathBuf::from(&home).join(".council");
-    fs::create_dir_all(&council_dir)?;
+    let council_dir = council_dir::find_council_dir()?;
 
     eprintln!();
     eprintln!("Commands installed successfully!");
-    eprintln!("Council directory: ~/.council/");
+    eprintln!("Council directory: {}", council_dir.display());
 
     Ok(())
 }
@@ -89,7 +114,7 @@ fn merge_mcp_config(config_path: &PathBuf) -> Result<bool> {
     };
 
     // Ensure mcpServers exists
-    if !config.get("mcpServers").is_some() {
+    if config.get("mcpServers").is_none() {
         config["mcpServers"] = json!({});
     }
 
@@ -202,8 +227,24 @@ async fn main() -> Result<()> {
             eprintln!("✅ Installation complete! Restart Claude Code to activate.");
             Ok(())
         }
+        Some("run") => {
+            let options = orchestrate::RunOptions::parse(&args[2..])?;
+            let final_path = orchestrate::run_council(&options).await?;
+            println!("{}", fs::read_to_string(&final_path)?.trim_end());
+            eprintln!("Final answer saved to {}", final_path.display());
+            Ok(())
+        }
+        Some("--http") => {
+            let allow_remote = args[2..].iter().any(|a| a == "--allow-remote");
+            let addr = args[2..]
+                .iter()
+                .map(|s| s.as_str())
+                .find(|a| *a != "--allow-remote")
+                .unwrap_or(DEFAULT_HTTP_ADDR);
+            http::serve(addr, allow_remote).await
+        }
         _ => {
-            let mut server = McpServer::new();
+            let server = Arc::new(McpServer::new());
             server.run().await
         }
     }
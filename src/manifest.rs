@@ -0,0 +1,596 @@
+use crate::engines::TokenUsage;
+use crate::ranking::{self, sha256_hex};
+use crate::tools::finalize::{list_review_files, read_stage2_review};
+use crate::tools::peer_review::{extract_user_query, list_answer_files, read_stage1_answer, same_model};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use fs4::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::OwnedMutexGuard;
+
+/// Per-session manifest kept in `{council}/{slug}/council.json`.
+pub const MANIFEST_FILE: &str = "council.json";
+
+/// Locked for the whole load → change → save of the manifest.
+const LOCK_FILE: &str = "council.json.lock";
+
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Complete,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageState {
+    pub status: StageStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stages {
+    pub stage1: StageState,
+    pub stage2: StageState,
+    pub stage3: StageState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerRecord {
+    pub model: String,
+    pub file: String,
+    pub sha256: String,
+    pub saved_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewRecord {
+    pub reviewer: String,
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranking_file: Option<String>,
+}
+
+impl ReviewRecord {
+    pub fn is_saved(&self) -> bool {
+        self.saved_at.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactRecord {
+    pub file: String,
+    pub sha256: String,
+    pub saved_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// A file handed to the chairman, hashed when the prompt was built.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputRecord {
+    pub file: String,
+    pub sha256: String,
+}
+
+/// The Stage1 answers and Stage2 reviews `council.finalize` put in the
+/// chairman prompt for one final answer file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalizeRequest {
+    pub output_file: String,
+    pub model: String,
+    pub requested_at: String,
+    pub stage1_answers: Vec<InputRecord>,
+    pub stage2_reviews: Vec<InputRecord>,
+}
+
+/// A model call made by `mcp-council run`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineRun {
+    /// `stage1`, `stage2` or `stage3`
+    pub stage: String,
+    /// The council member the call was made for
+    pub model: String,
+    /// The engine that answered; differs from `model` after a fallback
+    pub engine: String,
+    pub attempts: u32,
+    /// Token counts, when the engine reports them (HTTP engines)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    pub finished_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub participants: Vec<String>,
+    pub stages: Stages,
+    pub answers: Vec<AnswerRecord>,
+    pub reviews: Vec<ReviewRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ArtifactRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tally: Option<ArtifactRecord>,
+    #[serde(default)]
+    pub finals: Vec<ArtifactRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub finalize_requests: Vec<FinalizeRequest>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engine_runs: Vec<EngineRun>,
+}
+
+/// Exclusive access to a session's manifest, released when dropped. Take it
+/// before `load_or_rebuild` in any tool that saves.
+pub struct ManifestLock {
+    file: File,
+    /// Requests on one connection run in parallel; they queue here instead
+    /// of blocking runtime threads on the file lock
+    _session: OwnedMutexGuard<()>,
+}
+
+/// One queue per session directory for this process.
+fn session_mutex(base_dir: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+    let mut sessions = SESSIONS.get_or_init(Default::default).lock().unwrap();
+    Arc::clone(sessions.entry(base_dir.to_path_buf()).or_default())
+}
+
+impl Drop for ManifestLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).context(format!("Failed to read file: {}", path.display()))?;
+    Ok(sha256_hex(&bytes))
+}
+
+/// File name and SHA-256 of each file.
+pub fn describe_inputs(paths: &[PathBuf]) -> Result<Vec<InputRecord>> {
+    paths
+        .iter()
+        .map(|path| {
+            Ok(InputRecord {
+                file: file_name(path),
+                sha256: hash_file(path)?,
+            })
+        })
+        .collect()
+}
+
+impl Manifest {
+    pub fn new(title: &str) -> Self {
+        let created_at = now();
+        Self {
+            version: MANIFEST_VERSION,
+            title: title.to_string(),
+            query: None,
+            updated_at: created_at.clone(),
+            created_at,
+            participants: Vec::new(),
+            stages: Stages::default(),
+            answers: Vec::new(),
+            reviews: Vec::new(),
+            summary: None,
+            tally: None,
+            finals: Vec::new(),
+            finalize_requests: Vec::new(),
+            engine_runs: Vec::new(),
+        }
+    }
+
+    pub fn path(base_dir: &Path) -> PathBuf {
+        base_dir.join(MANIFEST_FILE)
+    }
+
+    /// Wait for exclusive access to the manifest in `base_dir`: first among
+    /// this process's requests, then against other processes.
+    pub async fn lock(base_dir: &Path) -> Result<ManifestLock> {
+        let session = session_mutex(base_dir).lock_owned().await;
+        let path = base_dir.join(LOCK_FILE);
+        // Another process may hold the file lock for a while; wait for it on
+        // a blocking thread so the runtime keeps serving other sessions
+        let file = tokio::task::spawn_blocking(move || -> Result<File> {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+                .context(format!("Failed to open lock file: {}", path.display()))?;
+            file.lock_exclusive()
+                .context(format!("Failed to lock {}", path.display()))?;
+            Ok(file)
+        })
+        .await
+        .context("Manifest lock task failed")??;
+        Ok(ManifestLock {
+            file,
+            _session: session,
+        })
+    }
+
+    pub fn load(base_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(base_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read manifest: {}", path.display()))?;
+        let manifest = serde_json::from_str(&content)
+            .context(format!("Failed to parse manifest: {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    /// Load the manifest, reconstructing it from the files on disk for
+    /// sessions created before manifests existed.
+    pub fn load_or_rebuild(base_dir: &Path, title: &str) -> Result<Self> {
+        match Self::load(base_dir)? {
+            Some(manifest) => Ok(manifest),
+            None => {
+                let mut manifest = Self::rebuild(base_dir, title)?;
+                manifest.save(base_dir)?;
+                Ok(manifest)
+            }
+        }
+    }
+
+    /// Like `load_or_rebuild` for read-only callers: a rebuilt manifest is
+    /// kept in memory and never written.
+    pub fn load_or_scan(base_dir: &Path, title: &str) -> Result<Self> {
+        match Self::load(base_dir)? {
+            Some(manifest) => Ok(manifest),
+            None => Self::rebuild(base_dir, title),
+        }
+    }
+
+    fn rebuild(base_dir: &Path, title: &str) -> Result<Self> {
+        let mut manifest = Self::new(title);
+
+        let answer_files = list_answer_files(base_dir)?;
+        if !answer_files.is_empty() {
+            log_info!(
+                "No {} in {}, rebuilding from directory contents",
+                MANIFEST_FILE,
+                base_dir.display()
+            );
+            manifest.query = Some(extract_user_query(base_dir)?);
+        }
+        for path in &answer_files {
+            let parsed = read_stage1_answer(path)?;
+            let model = parsed["model"].as_str().unwrap_or("unknown-model");
+            manifest.record_answer(model, path)?;
+        }
+
+        for path in &list_review_files(base_dir)? {
+            manifest.adopt_review(path)?;
+        }
+
+        let summary_path = base_dir.join("summary.md");
+        if summary_path.exists() {
+            manifest.record_summary(None, &summary_path)?;
+        }
+        let tally_path = base_dir.join("tally.json");
+        if tally_path.exists() {
+            manifest.record_tally(&tally_path)?;
+        }
+        for entry in fs::read_dir(base_dir)?.flatten() {
+            let path = entry.path();
+            let name = file_name(&path);
+            if let Some(model) = name
+                .strip_prefix("final-answer-by-")
+                .and_then(|n| n.strip_suffix(".md"))
+            {
+                manifest.record_final(model, &path)?;
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Write the manifest atomically so concurrent readers never see a
+    /// half-written file.
+    pub fn save(&mut self, base_dir: &Path) -> Result<()> {
+        self.updated_at = now();
+        let path = Self::path(base_dir);
+        // Unique per save, so concurrent writers never share a temp file
+        static SAVES: AtomicU64 = AtomicU64::new(0);
+        let tmp = base_dir.join(format!(
+            ".{}.{}.{}.tmp",
+            MANIFEST_FILE,
+            std::process::id(),
+            SAVES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .context(format!("Failed to write manifest: {}", tmp.display()))?;
+        fs::rename(&tmp, &path)
+            .context(format!("Failed to write manifest: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The canonical user question for the session.
+    pub fn query(&self) -> &str {
+        self.query.as_deref().unwrap_or("Unknown query")
+    }
+
+    pub fn answer_paths(&self, base_dir: &Path) -> Vec<PathBuf> {
+        self.answers.iter().map(|a| base_dir.join(&a.file)).collect()
+    }
+
+    /// Saved reviews only; requested-but-unsaved reviews have no file yet.
+    pub fn review_paths(&self, base_dir: &Path) -> Vec<PathBuf> {
+        self.reviews
+            .iter()
+            .filter(|r| r.is_saved())
+            .map(|r| base_dir.join(&r.file))
+            .collect()
+    }
+
+    fn touch_stage(stage: &mut StageState, status: StageStatus) {
+        stage.status = status;
+        stage.updated_at = Some(now());
+    }
+
+    pub fn record_answer(&mut self, model: &str, path: &Path) -> Result<()> {
+        let record = AnswerRecord {
+            model: model.to_string(),
+            file: file_name(path),
+            sha256: hash_file(path)?,
+            saved_at: now(),
+        };
+        self.answers.retain(|a| a.file != record.file);
+        self.answers.push(record);
+        // Keep file-name order so label assignment stays stable
+        self.answers.sort_by(|a, b| a.file.cmp(&b.file));
+
+        if !self.participants.iter().any(|p| same_model(p, model)) {
+            self.participants.push(model.to_string());
+        }
+        if self.stages.stage1.status == StageStatus::Pending {
+            Self::touch_stage(&mut self.stages.stage1, StageStatus::InProgress);
+        }
+        Ok(())
+    }
+
+    fn review_mut(&mut self, reviewer: &str, file: &str) -> &mut ReviewRecord {
+        let idx = match self.reviews.iter().position(|r| r.file == file) {
+            Some(idx) => idx,
+            None => {
+                self.reviews.push(ReviewRecord {
+                    reviewer: reviewer.to_string(),
+                    file: file.to_string(),
+                    sha256: None,
+                    requested_at: None,
+                    saved_at: None,
+                    labels_file: None,
+                    ranking_file: None,
+                });
+                self.reviews.sort_by(|a, b| a.file.cmp(&b.file));
+                self.reviews.iter().position(|r| r.file == file).unwrap()
+            }
+        };
+        &mut self.reviews[idx]
+    }
+
+    /// A reviewer asked for the review prompt; Stage1 answers are now fixed.
+    pub fn record_review_request(&mut self, reviewer: &str, review_file: &str, labels_path: &Path) {
+        let record = self.review_mut(reviewer, review_file);
+        record.requested_at = Some(now());
+        record.labels_file = Some(file_name(labels_path));
+
+        Self::touch_stage(&mut self.stages.stage1, StageStatus::Complete);
+        if self.stages.stage2.status == StageStatus::Pending {
+            Self::touch_stage(&mut self.stages.stage2, StageStatus::InProgress);
+        }
+    }
+
+    pub fn record_review(&mut self, reviewer: &str, path: &Path, ranking_path: Option<&Path>) -> Result<()> {
+        let sha256 = hash_file(path)?;
+        let record = self.review_mut(reviewer, &file_name(path));
+        record.reviewer = reviewer.to_string();
+        record.sha256 = Some(sha256);
+        record.saved_at = Some(now());
+        record.ranking_file = ranking_path.map(file_name);
+
+        let all_reviewed = !self.participants.is_empty()
+            && self.participants.iter().all(|p| {
+                self.reviews
+                    .iter()
+                    .any(|r| r.is_saved() && same_model(&r.reviewer, p))
+            });
+        let status = if all_reviewed {
+            StageStatus::Complete
+        } else {
+            StageStatus::InProgress
+        };
+        Self::touch_stage(&mut self.stages.stage2, status);
+        Ok(())
+    }
+
+    /// Register a review file that was written without `council.save_review`
+    /// (e.g. saved directly by the client).
+    pub fn adopt_review(&mut self, path: &Path) -> Result<()> {
+        let parsed = read_stage2_review(path)?;
+        let reviewer = parsed["model"].as_str().unwrap_or("unknown-model").to_string();
+        let ranking_path = ranking::ranking_path_for(path);
+        let ranking_path = ranking_path.exists().then_some(ranking_path);
+        let labels_path = ranking::labels_path_for(path);
+        if labels_path.exists() {
+            self.review_mut(&reviewer, &file_name(path)).labels_file = Some(file_name(&labels_path));
+        }
+        self.record_review(&reviewer, path, ranking_path.as_deref())
+    }
+
+    /// Register Stage1 answer files that the manifest does not know about yet
+    /// (written by hand, or by a save that lost a race before locking).
+    pub fn adopt_untracked_answers(&mut self, base_dir: &Path) -> Result<bool> {
+        let mut adopted = false;
+        for path in list_answer_files(base_dir)? {
+            let name = file_name(&path);
+            if !self.answers.iter().any(|a| a.file == name) {
+                let parsed = read_stage1_answer(&path)?;
+                let model = parsed["model"].as_str().unwrap_or("unknown-model").to_string();
+                self.record_answer(&model, &path)?;
+                adopted = true;
+            }
+        }
+        Ok(adopted)
+    }
+
+    /// Register saved review files that the manifest does not know about yet.
+    pub fn adopt_untracked_reviews(&mut self, base_dir: &Path) -> Result<bool> {
+        let mut adopted = false;
+        for path in list_review_files(base_dir)? {
+            let name = file_name(&path);
+            if !self.reviews.iter().any(|r| r.file == name && r.is_saved()) {
+                self.adopt_review(&path)?;
+                adopted = true;
+            }
+        }
+        Ok(adopted)
+    }
+
+    pub fn record_summary(&mut self, model: Option<&str>, path: &Path) -> Result<()> {
+        self.summary = Some(ArtifactRecord {
+            file: file_name(path),
+            sha256: hash_file(path)?,
+            saved_at: now(),
+            model: model.map(|m| m.to_string()),
+        });
+        Ok(())
+    }
+
+    pub fn record_tally(&mut self, path: &Path) -> Result<()> {
+        self.tally = Some(ArtifactRecord {
+            file: file_name(path),
+            sha256: hash_file(path)?,
+            saved_at: now(),
+            model: None,
+        });
+        Ok(())
+    }
+
+    /// The chairman was handed the synthesis prompt built from `answers` and
+    /// `reviews`; `save_final` later reports them for `output_file`.
+    pub fn record_finalize_request(
+        &mut self,
+        model: &str,
+        output_file: &str,
+        answers: &[PathBuf],
+        reviews: &[PathBuf],
+    ) -> Result<()> {
+        let request = FinalizeRequest {
+            output_file: output_file.to_string(),
+            model: model.to_string(),
+            requested_at: now(),
+            stage1_answers: describe_inputs(answers)?,
+            stage2_reviews: describe_inputs(reviews)?,
+        };
+        self.finalize_requests.retain(|r| r.output_file != request.output_file);
+        self.finalize_requests.push(request);
+        if self.stages.stage3.status == StageStatus::Pending {
+            Self::touch_stage(&mut self.stages.stage3, StageStatus::InProgress);
+        }
+        Ok(())
+    }
+
+    pub fn finalize_request(&self, output_file: &str) -> Option<&FinalizeRequest> {
+        self.finalize_requests.iter().find(|r| r.output_file == output_file)
+    }
+
+    pub fn record_engine_run(
+        &mut self,
+        stage: &str,
+        model: &str,
+        engine: &str,
+        attempts: u32,
+        usage: Option<TokenUsage>,
+    ) {
+        self.engine_runs.push(EngineRun {
+            stage: stage.to_string(),
+            model: model.to_string(),
+            engine: engine.to_string(),
+            attempts,
+            usage,
+            finished_at: now(),
+        });
+    }
+
+    pub fn record_final(&mut self, model: &str, path: &Path) -> Result<()> {
+        let record = ArtifactRecord {
+            file: file_name(path),
+            sha256: hash_file(path)?,
+            saved_at: now(),
+            model: Some(model.to_string()),
+        };
+        self.finals.retain(|f| f.file != record.file);
+        self.finals.push(record);
+        Self::touch_stage(&mut self.stages.stage3, StageStatus::Complete);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::Duration;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mcp-council-manifest-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn waiting_for_the_file_lock_does_not_block_the_runtime() {
+        let dir = scratch("lock");
+        // Stands in for another process holding the manifest
+        let held = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join(LOCK_FILE)).unwrap();
+        held.lock_exclusive().unwrap();
+
+        let waiting = tokio::spawn({
+            let dir = dir.clone();
+            async move { Manifest::lock(&dir).await.map(drop) }
+        });
+        // This single-threaded runtime only gets here if the waiting task
+        // yielded instead of blocking the thread
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        FileExt::unlock(&held).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), waiting).await.unwrap().unwrap().unwrap();
+    }
+}
@@ -1,7 +1,53 @@
+use crate::client::{ClientPeer, Outgoing, ORIGIN};
+use crate::logging::{self, Level, CONNECTION};
+use crate::progress::Progress;
+use crate::prompts::PromptError;
+use crate::resources::ResourceNotFound;
+use crate::schema::InvalidArguments;
+use crate::slug::InvalidSlug;
+use crate::tools::{ToolContext, ToolRegistry};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::{AbortHandle, JoinError, JoinSet};
+
+/// Protocol revisions this server speaks, newest first. Revisions are dates,
+/// so they compare as strings.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// First revision with structured tool output (`outputSchema`,
+/// `structuredContent`).
+const STRUCTURED_OUTPUT_VERSION: &str = "2025-06-18";
+
+/// What the client is told in `initialize` about how to use the server.
+const INSTRUCTIONS: &str = "mcp-council runs a three-stage LLM council under one title (a lower-case slug). \
+Stage1: each model answers the question and saves it with council.first_answer. \
+Stage2: each model calls council.peer_review to get the other answers anonymized, \
+ranks them ending with a FINAL RANKING section, and saves the review with council.save_review. \
+Stage3: one model calls council.finalize as chairman and saves the synthesis with council.save_final. \
+council.status shows what is missing at any point and council.tally aggregates the rankings. \
+When the client supports sampling, peer_review and finalize run the model themselves and save the result \
+(action ending in _saved). Use council.summarize and council.save_summary to shrink long documents first.";
+
+/// The newest revision both sides speak: the client's own when we support
+/// it, else the newest of ours that predates it. A client older than every
+/// revision we know gets our newest and decides whether to continue.
+fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    requested
+        .and_then(|requested| {
+            SUPPORTED_PROTOCOL_VERSIONS
+                .iter()
+                .find(|&&supported| supported <= requested)
+        })
+        .copied()
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0])
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct McpRequest {
@@ -30,457 +76,634 @@ struct McpError {
     data: Option<Value>,
 }
 
-pub struct McpServer;
+/// A request whose `params` lack something the method needs.
+#[derive(Debug, Error)]
+#[error("{0}")]
+struct InvalidParams(&'static str);
+
+/// A response carrying only an error.
+fn error_response(id: Option<Value>, code: i32, message: String) -> McpResponse {
+    McpResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Some(id.unwrap_or(Value::Null)),
+        result: None,
+        error: Some(McpError {
+            code,
+            message,
+            data: None,
+        }),
+    }
+}
+
+fn to_line(response: &McpResponse) -> Option<String> {
+    match serde_json::to_string(response) {
+        Ok(json) => Some(json),
+        Err(e) => {
+            log_error!("Failed to serialize response: {}", e);
+            None
+        }
+    }
+}
+
+/// The id to answer a message with: requests carry a string or number;
+/// anything else is answered with `null`.
+pub fn usable_id(message: &Value) -> Option<&Value> {
+    message.get("id").filter(|id| id.is_string() || id.is_number())
+}
+
+/// Check the JSON-RPC envelope of a request or notification. Returns the
+/// reason it is not one, for a -32600 response.
+pub fn check_envelope(message: &Value) -> Result<(), &'static str> {
+    let Some(object) = message.as_object() else {
+        return Err("Request must be a JSON object");
+    };
+    if object.get("jsonrpc").and_then(|v| v.as_str()) != Some("2.0") {
+        return Err("jsonrpc must be \"2.0\"");
+    }
+    if !object.get("method").is_some_and(|m| m.is_string()) {
+        return Err("method must be a string");
+    }
+    // MCP, unlike plain JSON-RPC, does not allow a null id
+    if object.get("id").is_some_and(|id| !(id.is_string() || id.is_number())) {
+        return Err("id must be a string or a number");
+    }
+    if object.get("params").is_some_and(|p| !(p.is_object() || p.is_array())) {
+        return Err("params must be an object or an array");
+    }
+    Ok(())
+}
+
+/// Map a handler failure to a JSON-RPC error. Bad arguments (such as an
+/// unsafe `title`) are invalid params, unknown resource URIs are -32002, and
+/// everything else is an internal error.
+fn handler_error(e: &anyhow::Error, message: String) -> McpError {
+    if e.is::<InvalidParams>() {
+        return McpError {
+            code: -32602,
+            message,
+            data: None,
+        };
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidArguments>() {
+        return McpError {
+            code: -32602,
+            message,
+            data: Some(invalid.to_error_data()),
+        };
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidSlug>() {
+        return McpError {
+            code: -32602,
+            message,
+            data: Some(invalid.to_error_data()),
+        };
+    }
+    if let Some(prompt) = e.downcast_ref::<PromptError>() {
+        return McpError {
+            code: -32602,
+            message,
+            data: Some(prompt.to_error_data()),
+        };
+    }
+    if let Some(missing) = e.downcast_ref::<ResourceNotFound>() {
+        return McpError {
+            code: -32002,
+            message,
+            data: Some(missing.to_error_data()),
+        };
+    }
+    McpError {
+        code: -32603,
+        message,
+        data: None,
+    }
+}
+
+fn log_task_failure(joined: Result<(), JoinError>) {
+    if let Err(e) = joined {
+        if !e.is_cancelled() {
+            log_error!("Request task failed: {}", e);
+        }
+    }
+}
+
+/// A request that is still being handled, keyed by its JSON-RPC id so
+/// `notifications/cancelled` can abort it.
+struct InFlight {
+    seq: u64,
+    abort: AbortHandle,
+}
+
+/// The responses of one batch, sent as a single array once every request
+/// in it has finished or been cancelled (that is, when the last task
+/// holding the batch drops it). A batch of notifications sends nothing.
+struct Batch {
+    client: Arc<ClientPeer>,
+    responses: Mutex<Vec<String>>,
+}
+
+impl Batch {
+    fn push(&self, response: String) {
+        self.responses.lock().unwrap().push(response);
+    }
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        let responses = std::mem::take(&mut *self.responses.lock().unwrap());
+        if !responses.is_empty() {
+            self.client
+                .complete(None, Some(format!("[{}]", responses.join(","))));
+        }
+    }
+}
+
+pub struct McpServer {
+    client: Arc<ClientPeer>,
+    outgoing: Mutex<Option<mpsc::UnboundedReceiver<Outgoing>>>,
+    in_flight: Mutex<HashMap<String, InFlight>>,
+    seq: AtomicU64,
+    tools: ToolRegistry,
+    /// Revision agreed on in `initialize`
+    protocol_version: Mutex<&'static str>,
+}
 
 impl McpServer {
     pub fn new() -> Self {
-        Self
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            client: Arc::new(ClientPeer::new(tx)),
+            outgoing: Mutex::new(Some(rx)),
+            in_flight: Mutex::new(HashMap::new()),
+            seq: AtomicU64::new(0),
+            tools: ToolRegistry::new(),
+            protocol_version: Mutex::new(SUPPORTED_PROTOCOL_VERSIONS[0]),
+        }
     }
 
-    pub async fn run(&mut self) -> Result<()> {
-        let stdin = tokio::io::stdin();
-        let mut reader = BufReader::new(stdin);
-        let mut stdout = tokio::io::stdout();
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
 
-        let mut buffer = String::new();
+    /// Everything the server sends, in order: responses, client requests
+    /// such as sampling, and notifications. Can be taken once, by the
+    /// transport.
+    pub fn take_outgoing(&self) -> Result<mpsc::UnboundedReceiver<Outgoing>> {
+        self.outgoing
+            .lock()
+            .unwrap()
+            .take()
+            .context("Server is already running")
+    }
 
-        eprintln!("DEBUG: MCP server started, waiting for requests...");
+    /// Close the connection to the client: requests waiting on it fail and
+    /// the outgoing channel ends once drained.
+    pub fn shutdown(&self) {
+        self.client.close();
+    }
 
-        loop {
-            buffer.clear();
-            let bytes_read = reader.read_line(&mut buffer).await?;
+    /// Serve newline-delimited JSON-RPC over any byte stream pair. A writer
+    /// task owns the output so responses and server-to-client requests never
+    /// interleave; responses to our own requests are routed to the waiting
+    /// handler as soon as they are read. Each request runs on its own task,
+    /// so a slow tool does not hold up `ping` or other calls.
+    pub async fn serve<R, W>(self: Arc<Self>, input: R, output: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut outgoing = self.take_outgoing()?;
+        let writer = tokio::spawn(async move {
+            let mut output = output;
+            while let Some(Outgoing { message, .. }) = outgoing.recv().await {
+                let Some(message) = message else { continue };
+                log_debug!("Sending message: {}", logging::payload_str(&message));
+                output.write_all(message.as_bytes()).await?;
+                output.write_all(b"\n").await?;
+                output.flush().await?;
+            }
+            anyhow::Ok(())
+        });
+
+        // Read on a separate task so a handler waiting on the client (e.g.
+        // for sampling) does not stop its response from being read
+        let (lines_tx, mut lines) = mpsc::unbounded_channel::<String>();
+        let reader = tokio::spawn(async move {
+            let mut reader = BufReader::new(input);
+            let mut buffer = String::new();
+            loop {
+                buffer.clear();
+                if reader.read_line(&mut buffer).await? == 0 {
+                    log_debug!("EOF received, shutting down");
+                    break;
+                }
 
-            if bytes_read == 0 {
-                eprintln!("DEBUG: EOF received, shutting down");
-                break; // EOF
+                let line = buffer.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                log_debug!("Received line: {}", logging::payload_str(line));
+                if lines_tx.send(line.to_string()).is_err() {
+                    break;
+                }
             }
+            anyhow::Ok(())
+        });
+
+        log_debug!("MCP server started, waiting for requests...");
 
-            let line = buffer.trim();
-            if line.is_empty() {
-                continue;
+        let mut tasks = JoinSet::new();
+        loop {
+            tokio::select! {
+                line = lines.recv() => match line {
+                    Some(line) => self.accept(&mut tasks, line),
+                    None => break,
+                },
+                Some(joined) = tasks.join_next() => log_task_failure(joined),
             }
+        }
 
-            eprintln!("DEBUG: Received line: {}", line);
+        // Finish what is in flight; nothing more will arrive from the client,
+        // so requests waiting on it fail instead of timing out
+        self.client.cancel_pending();
+        while let Some(joined) = tasks.join_next().await {
+            log_task_failure(joined);
+        }
 
-            match self.handle_request(line).await {
-                Ok(Some(response)) => {
-                    let response_json = serde_json::to_string(&response)?;
-                    eprintln!("DEBUG: Sending response: {}", response_json);
-                    stdout.write_all(response_json.as_bytes()).await?;
-                    stdout.write_all(b"\n").await?;
-                    stdout.flush().await?;
-                }
-                Ok(None) => {
-                    // Notification (no id) or intentionally suppressed response
-                    eprintln!("DEBUG: Suppressed response (notification)");
+        self.shutdown();
+        reader.await??;
+        writer.await??;
+        Ok(())
+    }
+
+    /// Take one incoming line: a message or a batch of them. Input that is
+    /// not JSON gets a -32700 response and an empty batch a -32600 one.
+    pub fn accept(self: &Arc<Self>, tasks: &mut JoinSet<()>, line: String) {
+        let message = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                log_error!("Failed to parse JSON-RPC message: {}", e);
+                let response = error_response(None, -32700, format!("Parse error: {}", e));
+                self.client.complete(None, to_line(&response));
+                return;
+            }
+        };
+        match message {
+            Value::Array(items) if items.is_empty() => {
+                let response = error_response(None, -32600, "Invalid Request: empty batch".to_string());
+                self.client.complete(None, to_line(&response));
+            }
+            Value::Array(items) => {
+                let batch = Arc::new(Batch {
+                    client: Arc::clone(&self.client),
+                    responses: Mutex::new(Vec::new()),
+                });
+                for item in items {
+                    if let Some(response) = self.start(tasks, item, Some(Arc::clone(&batch)), true) {
+                        batch.push(response);
+                    }
                 }
-                Err(e) => {
-                    // For malformed input (e.g., non-JSON lines), log and skip without emitting a JSON response
-                    eprintln!("ERROR: Error handling request: {}", e);
-                    eprintln!("ERROR: Line was: {}", line);
+            }
+            message => {
+                if let Some(response) = self.start(tasks, message, None, true) {
+                    self.client.complete(None, Some(response));
                 }
             }
         }
+    }
 
-        Ok(())
+    /// Take one parsed message. Invalid requests are answered on the spot
+    /// with the returned -32600 response; the caller delivers it. Pass
+    /// `sampling: false` when the transport has no way to deliver a request
+    /// from the server while this message is handled; tools then see no
+    /// sampling client.
+    pub fn accept_message(
+        self: &Arc<Self>,
+        tasks: &mut JoinSet<()>,
+        message: Value,
+        sampling: bool,
+    ) -> Option<String> {
+        self.start(tasks, message, None, sampling)
     }
 
-    async fn handle_request(&self, line: &str) -> Result<Option<McpResponse>> {
-        eprintln!("DEBUG: Parsing request: {}", line);
-        let request: McpRequest = match serde_json::from_str(line) {
-            Ok(req) => req,
+    /// Responses to our own requests and cancellations are handled on the
+    /// spot; a request runs on its own task in `tasks`, registered under its
+    /// id so it can be cancelled. The task queues its response on the
+    /// outgoing channel behind anything the handler sent while running, or
+    /// adds it to `batch`.
+    fn start(
+        self: &Arc<Self>,
+        tasks: &mut JoinSet<()>,
+        message: Value,
+        batch: Option<Arc<Batch>>,
+        sampling: bool,
+    ) -> Option<String> {
+        if self.client.handle_response(&message) {
+            return None;
+        }
+        if let Err(reason) = check_envelope(&message) {
+            log_error!("Invalid JSON-RPC request ({}): {}", reason, logging::payload(&message));
+            let id = usable_id(&message).cloned();
+            return to_line(&error_response(id, -32600, format!("Invalid Request: {}", reason)));
+        }
+        if message["method"] == "notifications/cancelled" {
+            self.cancel(&message["params"]);
+            return None;
+        }
+        let key = usable_id(&message).map(|id| id.to_string());
+
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        let server = Arc::clone(self);
+        let task_key = key.clone();
+        // Hold the lock across spawn so the task cannot finish and
+        // deregister before it is registered
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let client = Arc::clone(&self.client);
+        let abort = tasks.spawn(ORIGIN.scope(key.clone(), CONNECTION.scope(client, async move {
+            let response = server.dispatch(message, sampling).await;
+            // Only requests with a usable id get a response
+            if let Some(key) = task_key {
+                {
+                    let mut in_flight = server.in_flight.lock().unwrap();
+                    if in_flight.get(&key).is_some_and(|f| f.seq == seq) {
+                        in_flight.remove(&key);
+                    }
+                }
+                match &batch {
+                    Some(batch) => response.into_iter().for_each(|r| batch.push(r)),
+                    None => server.client.complete(Some(key), response),
+                }
+            }
+        })));
+        if let Some(key) = key {
+            in_flight.insert(key, InFlight { seq, abort });
+        }
+        None
+    }
+
+    /// Handle one request and serialize its response, if any. A handler
+    /// error becomes a JSON-RPC error for requests and is logged for
+    /// notifications.
+    async fn dispatch(&self, message: Value, sampling: bool) -> Option<String> {
+        let id = usable_id(&message).cloned();
+        match self.handle_request(message, sampling).await {
+            Ok(Some(response)) => to_line(&response),
+            Ok(None) => {
+                // Notification (no id) or intentionally suppressed response
+                log_debug!("Suppressed response (notification)");
+                None
+            }
             Err(e) => {
-                eprintln!("ERROR: Failed to parse JSON-RPC request: {}", e);
-                eprintln!("ERROR: Input was: {}", line);
-                return Err(anyhow::anyhow!("Failed to parse JSON-RPC request: {}", e));
+                log_error!("Error handling request: {}", e);
+                id.and_then(|id| {
+                    to_line(&McpResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: Some(id),
+                        result: None,
+                        error: Some(handler_error(&e, e.to_string())),
+                    })
+                })
             }
+        }
+    }
+
+    /// Abort the handler for `params.requestId`. Per the MCP spec no
+    /// response is sent for a cancelled request, and unknown or already
+    /// finished ids are ignored.
+    fn cancel(&self, params: &Value) {
+        let Some(id) = params
+            .get("requestId")
+            .filter(|id| id.is_string() || id.is_number())
+        else {
+            log_warn!("notifications/cancelled without a valid requestId");
+            return;
         };
-        
-        eprintln!("DEBUG: Parsed method: {}, id: {:?}", request.method, request.id);
-
-        let mut request_id = request.id.clone();
-        let is_notification = match request_id.as_ref() {
-            None => true,
-            Some(v) if v.is_null() => true,
-            Some(v) if v.is_boolean() => true,
-            Some(v) if v.is_array() => true,
-            Some(v) if v.is_object() => true,
-            _ => false,
-        };
-        if is_notification && request_id.is_some() {
-            eprintln!(
-                "Invalid JSON-RPC id (ignored, treated as notification): {:?}",
-                request_id
-            );
-            request_id = None;
+        let reason = params["reason"].as_str().unwrap_or("no reason given");
+        let key = id.to_string();
+        let in_flight = self.in_flight.lock().unwrap().remove(&key);
+        match in_flight {
+            Some(in_flight) => {
+                in_flight.abort.abort();
+                // No response, but transports still learn the request is over
+                self.client.complete(Some(key), None);
+                log_debug!("Cancelled request {} ({})", id, reason);
+            }
+            None => log_debug!("Cancel for unknown or finished request {} ignored", id),
         }
-        let response_id = if is_notification { None } else { request_id.clone() };
+    }
+
+    /// Whether the negotiated revision has `outputSchema` and
+    /// `structuredContent`.
+    fn structured_output(&self) -> bool {
+        *self.protocol_version.lock().unwrap() >= STRUCTURED_OUTPUT_VERSION
+    }
+
+    /// The client, when it advertised the `sampling` capability and the
+    /// transport can reach it.
+    fn sampler(&self, sampling: bool) -> Option<&ClientPeer> {
+        Some(self.client.as_ref()).filter(|c| sampling && c.supports_sampling())
+    }
+
+    async fn handle_request(&self, message: Value, sampling: bool) -> Result<Option<McpResponse>> {
+        log_debug!("Handling request: {}", logging::payload(&message));
+        let request: McpRequest = serde_json::from_value(message)?;
+        log_debug!("Parsed method: {}, id: {:?}", request.method, request.id);
+
+        // The envelope was checked on arrival: the id is a string or number
+        let is_notification = request.id.is_none();
+        let response_id = request.id.clone();
 
         let result = match request.method.as_str() {
             "initialize" => {
-                eprintln!("DEBUG: Received initialize request");
+                log_debug!("Received initialize request");
+                if let Some(capabilities) = request.params.as_ref().and_then(|p| p.get("capabilities")) {
+                    self.client.set_capabilities(capabilities.clone());
+                }
+                let requested = request
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("protocolVersion"))
+                    .and_then(|v| v.as_str());
+                let protocol_version = negotiate_protocol_version(requested);
+                *self.protocol_version.lock().unwrap() = protocol_version;
+                log_debug!(
+                    "Negotiated protocol version {} (client requested {:?})",
+                    protocol_version, requested
+                );
                 Some(json!({
-                    "protocolVersion": "2024-11-05",
+                    "protocolVersion": protocol_version,
                     "capabilities": {
-                        "tools": {}
+                        "tools": {},
+                        "resources": {},
+                        "prompts": {},
+                        "logging": {}
                     },
                     "serverInfo": {
-                        "name": "mcp-council",
-                        "version": "0.1.0"
-                    }
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION")
+                    },
+                    "instructions": INSTRUCTIONS
                 }))
             }
+            "ping" => Some(json!({})),
+            "logging/setLevel" => {
+                let params = request.params.ok_or(InvalidParams("Missing params"))?;
+                let level: Level = params["level"]
+                    .as_str()
+                    .and_then(|level| level.parse().ok())
+                    .ok_or(InvalidParams(
+                        "level must be one of debug, info, notice, warning, error, critical, alert, emergency",
+                    ))?;
+                self.client.set_log_level(level);
+                log_debug!("Client log level set to {}", level);
+                Some(json!({}))
+            }
             "initialized" => {
                 // MCP protocol: initialized is a notification, no response needed
-                eprintln!("DEBUG: Received initialized notification");
+                log_debug!("Received initialized notification");
                 return Ok(None);
             }
-            "tools/list" => {
-                Some(json!({
-                    "tools": [
-                        {
-                            "name": "council.first_answer",
-                            "description": "Stage1: Save current model answer into .council/{slug}/{model}-answer.md",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "title": {
-                                        "type": "string",
-                                        "description": "Conversation title/directory name (slug)"
-                                    },
-                                    "model": {
-                                        "type": "string",
-                                        "description": "Model name (e.g., sonnet, gemini, gpt-5.1)",
-                                        "default": "unknown-model"
-                                    },
-                                    "prompt": {
-                                        "type": "string",
-                                        "description": "User question or prompt text"
-                                    },
-                                    "content": {
-                                        "type": "string",
-                                        "description": "Full model answer content to save"
-                                    }
-                                },
-                                "required": ["title", "prompt", "content"]
-                            }
-                        },
-                        {
-                            "name": "council.peer_review",
-                            "description": "Stage2: Read Stage1 JSON files and generate peer review using local LLM CLI",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "title": {
-                                        "type": "string",
-                                        "description": "Conversation title/directory name"
-                                    },
-                                    "model": {
-                                        "type": "string",
-                                        "description": "LLM model name performing the review (examples: claude, gemini, glm-4.6)",
-                                        "default": "claude"
-                                    },
-                                    "self_model": {
-                                        "type": "string",
-                                        "description": "Model name to exclude from peer review (its own response)"
-                                    }
-                                },
-                                "required": ["title"]
-                            }
-                        },
-                        {
-                            "name": "council.finalize",
-                            "description": "Stage3: Read Stage1 and Stage2 JSON files and generate final answer using local LLM CLI",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "title": {
-                                        "type": "string",
-                                        "description": "Conversation title/directory name"
-                                    },
-                                    "model": {
-                                        "type": "string",
-                                        "description": "LLM model name performing the synthesis (examples: claude, gemini, glm-4.6)",
-                                        "default": "claude"
-                                    },
-                                    "engine": {
-                                        "type": "string",
-                                        "description": "LLM model/engine (for backward compatibility, use 'model' instead)",
-                                        "default": "claude"
-                                    }
-                                },
-                                "required": ["title"]
-                            }
-                        },
-                        {
-                            "name": "council.save_review",
-                            "description": "Save peer review content to markdown file",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "title": {
-                                        "type": "string",
-                                        "description": "Conversation title/directory name"
-                                    },
-                                    "model": {
-                                        "type": "string",
-                                        "description": "LLM model name (examples: claude, gemini, glm-4.6, gpt-4)"
-                                    },
-                                    "engine": {
-                                        "type": "string",
-                                        "description": "LLM model/engine name (for backward compatibility, use 'model' instead)",
-                                        "default": "claude"
-                                    },
-                                    "content": {
-                                        "type": "string",
-                                        "description": "Peer review content to save"
-                                    }
-                                },
-                                "required": ["title", "content"]
-                            }
-                        },
-                        {
-                            "name": "council.summarize",
-                            "description": "Generate a summary prompt for large documents to reduce token costs in Stage2/Stage3",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "title": {
-                                        "type": "string",
-                                        "description": "Conversation title/directory name (slug)"
-                                    },
-                                    "model": {
-                                        "type": "string",
-                                        "description": "Model name performing the summary (e.g., sonnet, gemini, gpt-5.1)",
-                                        "default": "unknown-model"
-                                    },
-                                    "content": {
-                                        "type": "string",
-                                        "description": "Original content to summarize"
-                                    },
-                                    "max_length": {
-                                        "type": "integer",
-                                        "description": "Target summary length in characters (default: 2000)",
-                                        "default": 2000
-                                    }
-                                },
-                                "required": ["title", "content"]
-                            }
-                        },
-                        {
-                            "name": "council.save_summary",
-                            "description": "Save summary content to markdown file for use in Stage2/Stage3",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "title": {
-                                        "type": "string",
-                                        "description": "Conversation title/directory name"
-                                    },
-                                    "model": {
-                                        "type": "string",
-                                        "description": "Model name that generated the summary"
-                                    },
-                                    "content": {
-                                        "type": "string",
-                                        "description": "Summary content to save"
-                                    }
-                                },
-                                "required": ["title", "content"]
-                            }
-                        }
-                    ]
-                }))
-            }
+            "tools/list" => Some(self.tools.list(self.structured_output())),
             "tools/call" => {
-                let params = request.params.context("Missing params")?;
+                let params = request.params.ok_or(InvalidParams("Missing params"))?;
                 let tool_name = params["name"]
                     .as_str()
-                    .context("Missing tool name")?;
+                    .ok_or(InvalidParams("Missing tool name"))?;
                 let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+                let progress = Progress::new(
+                    Arc::clone(&self.client),
+                    params.get("_meta").and_then(|m| m.get("progressToken")).cloned(),
+                );
 
-                match tool_name {
-                    "council.first_answer" => {
-                        match crate::tools::first_answer::handle_first_answer(arguments).await {
-                            Ok(result) => Some(json!({
-                                "content": [
-                                    {
-                                        "type": "text",
-                                        "text": serde_json::to_string(&result)?
-                                    }
-                                ]
-                            })),
-                            Err(e) => {
-                                if is_notification {
-                                    eprintln!("Stage1 save failed for notification: {}", e);
-                                    return Ok(None);
-                                }
-                                return Ok(Some(McpResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: response_id.clone(),
-                                    result: None,
-                                    error: Some(McpError {
-                                        code: -32603,
-                                        message: format!("Stage1 save failed: {}", e),
-                                        data: None,
-                                    }),
-                                }));
-                            }
-                        }
+                let Some(tool) = self.tools.get(tool_name) else {
+                    if is_notification {
+                        return Ok(None);
                     }
-                    "council.peer_review" => {
-                        match crate::tools::peer_review::handle_peer_review(arguments).await {
-                            Ok(result) => Some(json!({
-                                "content": [
-                                    {
-                                        "type": "text",
-                                        "text": serde_json::to_string(&result)?
-                                    }
-                                ]
-                            })),
-                            Err(e) => {
-                                if is_notification {
-                                    eprintln!("Peer review failed for notification: {}", e);
-                                    return Ok(None);
+                    return Ok(Some(McpResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: response_id.clone(),
+                        result: None,
+                        error: Some(McpError {
+                            code: -32601,
+                            message: format!("Unknown tool: {}", tool_name),
+                            data: None,
+                        }),
+                    }));
+                };
+
+                // Arguments that do not fit the schema never reach the handler
+                let checked = crate::schema::validate_arguments(tool_name, &tool.input_schema(), &arguments)
+                    .map_err(anyhow::Error::from);
+                let ctx = ToolContext {
+                    sampler: self.sampler(sampling),
+                    progress: &progress,
+                };
+                let outcome = match checked {
+                    Ok(()) => tool.call(arguments, &ctx).await,
+                    Err(e) => Err(e),
+                };
+                match outcome {
+                    Ok(result) => {
+                        let mut response = json!({
+                            "content": [
+                                {
+                                    "type": "text",
+                                    "text": serde_json::to_string(&result)?
                                 }
-                                return Ok(Some(McpResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: response_id.clone(),
-                                    result: None,
-                                    error: Some(McpError {
-                                        code: -32603,
-                                        message: format!("Peer review failed: {}", e),
-                                        data: None,
-                                    }),
-                                }));
-                            }
+                            ]
+                        });
+                        if self.structured_output() {
+                            response["structuredContent"] = result;
                         }
+                        Some(response)
                     }
-                    "council.finalize" => {
-                        match crate::tools::finalize::handle_finalize(arguments).await {
-                            Ok(result) => Some(json!({
-                                "content": [
-                                    {
-                                        "type": "text",
-                                        "text": serde_json::to_string(&result)?
-                                    }
-                                ]
-                            })),
-                            Err(e) => {
-                                if is_notification {
-                                    eprintln!("Finalize failed for notification: {}", e);
-                                    return Ok(None);
-                                }
-                                return Ok(Some(McpResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: response_id.clone(),
-                                    result: None,
-                                    error: Some(McpError {
-                                        code: -32603,
-                                        message: format!("Finalize failed: {}", e),
-                                        data: None,
-                                    }),
-                                }));
-                            }
-                        }
+                    Err(e) if is_notification => {
+                        log_warn!("{} for notification: {}", tool.failure_message(), e);
+                        return Ok(None);
                     }
-                    "council.save_review" => {
-                        match crate::tools::save_review::handle_save_review(arguments).await {
-                            Ok(result) => Some(json!({
-                                "content": [
-                                    {
-                                        "type": "text",
-                                        "text": serde_json::to_string(&result)?
-                                    }
-                                ]
-                            })),
-                            Err(e) => {
-                                if is_notification {
-                                    eprintln!("Save review failed for notification: {}", e);
-                                    return Ok(None);
-                                }
-                                return Ok(Some(McpResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: response_id.clone(),
-                                    result: None,
-                                    error: Some(McpError {
-                                        code: -32603,
-                                        message: format!("Save review failed: {}", e),
-                                        data: None,
-                                    }),
-                                }));
-                            }
-                        }
+                    // Bad arguments are the caller's mistake: a protocol error
+                    Err(e) if e.is::<InvalidArguments>() || e.is::<InvalidSlug>() => {
+                        let message = if e.is::<InvalidArguments>() {
+                            e.to_string()
+                        } else {
+                            format!("{}: {}", tool.failure_message(), e)
+                        };
+                        return Ok(Some(McpResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: response_id.clone(),
+                            result: None,
+                            error: Some(handler_error(&e, message)),
+                        }));
                     }
-                    "council.summarize" => {
-                        match crate::tools::summarize::handle_summarize(arguments).await {
-                            Ok(result) => Some(json!({
-                                "content": [
-                                    {
-                                        "type": "text",
-                                        "text": serde_json::to_string(&result)?
-                                    }
-                                ]
-                            })),
-                            Err(e) => {
-                                if is_notification {
-                                    eprintln!("Summarize failed for notification: {}", e);
-                                    return Ok(None);
+                    // The tool ran and failed: report it in the result so the
+                    // model can see what went wrong and react
+                    Err(e) => {
+                        log_error!("{}: {:#}", tool.failure_message(), e);
+                        Some(json!({
+                            "content": [
+                                {
+                                    "type": "text",
+                                    "text": format!("{}: {}", tool.failure_message(), e)
                                 }
-                                return Ok(Some(McpResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: response_id.clone(),
-                                    result: None,
-                                    error: Some(McpError {
-                                        code: -32603,
-                                        message: format!("Summarize failed: {}", e),
-                                        data: None,
-                                    }),
-                                }));
-                            }
-                        }
+                            ],
+                            "isError": true
+                        }))
                     }
-                    "council.save_summary" => {
-                        match crate::tools::save_summary::handle_save_summary(arguments).await {
-                            Ok(result) => Some(json!({
-                                "content": [
-                                    {
-                                        "type": "text",
-                                        "text": serde_json::to_string(&result)?
-                                    }
-                                ]
-                            })),
-                            Err(e) => {
-                                if is_notification {
-                                    eprintln!("Save summary failed for notification: {}", e);
-                                    return Ok(None);
-                                }
-                                return Ok(Some(McpResponse {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: response_id.clone(),
-                                    result: None,
-                                    error: Some(McpError {
-                                        code: -32603,
-                                        message: format!("Save summary failed: {}", e),
-                                        data: None,
-                                    }),
-                                }));
-                            }
+                }
+            }
+            "resources/list" => match crate::resources::list_resources() {
+                Ok(result) => Some(result),
+                Err(e) => {
+                    if is_notification {
+                        log_warn!("List resources failed for notification: {}", e);
+                        return Ok(None);
+                    }
+                    return Ok(Some(McpResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: response_id.clone(),
+                        result: None,
+                        error: Some(handler_error(&e, format!("List resources failed: {}", e))),
+                    }));
+                }
+            },
+            "resources/templates/list" => Some(crate::resources::list_templates()),
+            "resources/read" => {
+                let params = request.params.ok_or(InvalidParams("Missing params"))?;
+                let uri = params["uri"].as_str().ok_or(InvalidParams("Missing resource uri"))?;
+                match crate::resources::read_resource(uri) {
+                    Ok(result) => Some(result),
+                    Err(e) => {
+                        if is_notification {
+                            log_warn!("Read resource failed for notification: {}", e);
+                            return Ok(None);
                         }
+                        return Ok(Some(McpResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: response_id.clone(),
+                            result: None,
+                            error: Some(handler_error(&e, format!("Read resource failed: {}", e))),
+                        }));
                     }
-                    _ => {
+                }
+            }
+            "prompts/list" => Some(crate::prompts::list_prompts()),
+            "prompts/get" => {
+                let params = request.params.ok_or(InvalidParams("Missing params"))?;
+                let name = params["name"].as_str().ok_or(InvalidParams("Missing prompt name"))?;
+                let arguments = params["arguments"].as_object().cloned().unwrap_or_default();
+                match crate::prompts::get_prompt(name, &arguments) {
+                    Ok(result) => Some(result),
+                    Err(e) => {
                         if is_notification {
+                            log_warn!("Get prompt failed for notification: {}", e);
                             return Ok(None);
                         }
                         return Ok(Some(McpResponse {
                             jsonrpc: "2.0".to_string(),
                             id: response_id.clone(),
                             result: None,
-                            error: Some(McpError {
-                                code: -32601,
-                                message: format!("Unknown tool: {}", tool_name),
-                                data: None,
-                            }),
+                            error: Some(handler_error(&e, format!("Get prompt failed: {}", e))),
                         }));
                     }
                 }
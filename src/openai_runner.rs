@@ -0,0 +1,311 @@
+use crate::cli_runner::{EngineError, STDERR_TAIL_BYTES};
+use crate::engines::{EngineConfig, OpenAiConfig, TokenUsage};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// `{base_url}/chat/completions`, tolerating a trailing slash.
+pub fn completions_url(base_url: &str) -> String {
+    format!("{}/chat/completions", base_url.trim_end_matches('/'))
+}
+
+/// Ask an OpenAI-compatible endpoint to answer `prompt` as a single user
+/// message. Returns the first choice's content and the reported token usage.
+///
+/// `engine` supplies the timeout and the cap on the response body; dropping
+/// the future abandons the request.
+pub async fn run_openai(
+    api: &OpenAiConfig,
+    engine: &EngineConfig,
+    prompt: &str,
+) -> Result<(String, Option<TokenUsage>)> {
+    let url = completions_url(&api.base_url);
+    let mut body = json!({
+        "model": api.model,
+        "messages": [{ "role": "user", "content": prompt }],
+    });
+    if let Some(max_tokens) = api.max_tokens {
+        body["max_tokens"] = json!(max_tokens);
+    }
+    if let Some(temperature) = api.temperature {
+        body["temperature"] = json!(temperature);
+    }
+
+    let mut request = client()
+        .post(&url)
+        .timeout(Duration::from_secs(engine.timeout_secs))
+        .json(&body);
+    if let Some(var) = &api.api_key_env {
+        let key = env::var(var).context(format!("API key variable {} is not set", var))?;
+        request = request.bearer_auth(key);
+    }
+    log_debug!(
+        "POST {} (model {}, {} prompt chars, timeout {}s)",
+        url,
+        api.model,
+        prompt.len(),
+        engine.timeout_secs
+    );
+
+    let timed_out = |e: reqwest::Error| -> anyhow::Error {
+        if e.is_timeout() {
+            EngineError::Timeout {
+                command: url.clone(),
+                timeout_secs: engine.timeout_secs,
+            }
+            .into()
+        } else {
+            anyhow::Error::new(e).context(format!("Request to {} failed", url))
+        }
+    };
+
+    let mut response = request.send().await.map_err(timed_out)?;
+    let status = response.status();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(timed_out)? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > engine.max_output_bytes {
+            return Err(EngineError::ResponseTooLarge {
+                url,
+                limit: engine.max_output_bytes,
+            }
+            .into());
+        }
+    }
+
+    if !status.is_success() {
+        let tail = &bytes[bytes.len().saturating_sub(STDERR_TAIL_BYTES)..];
+        return Err(EngineError::HttpStatus {
+            url,
+            status: status.as_u16(),
+            body_tail: String::from_utf8_lossy(tail).trim().to_string(),
+        }
+        .into());
+    }
+
+    let parsed: Value =
+        serde_json::from_slice(&bytes).context(format!("{} did not return valid JSON", url))?;
+    let content = parsed["choices"][0]["message"]["content"]
+        .as_str()
+        .context(format!("No choices[0].message.content in the response from {}", url))?
+        .trim();
+    if content.is_empty() {
+        anyhow::bail!("{} returned an empty answer", url);
+    }
+    let usage = parsed
+        .get("usage")
+        .and_then(|usage| serde_json::from_value(usage.clone()).ok());
+    Ok((content.to_string(), usage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_runner::run_llm;
+    use crate::config::Config;
+    use crate::engines::EngineRegistry;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serve `router` on a free localhost port and return its `/v1` base URL.
+    async fn mock_server(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}/v1/", addr)
+    }
+
+    fn registry(engines: Value) -> EngineRegistry {
+        let config: Config = serde_json::from_value(json!({ "engines": engines })).unwrap();
+        EngineRegistry::from_config(&config)
+    }
+
+    fn completion(content: &str) -> Value {
+        json!({
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": content } }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 5, "total_tokens": 17 }
+        })
+    }
+
+    #[tokio::test]
+    async fn sends_the_prompt_and_reports_usage() {
+        env::set_var("MCP_COUNCIL_TEST_KEY", "sk-test");
+        let router = Router::new().route(
+            "/v1/chat/completions",
+            post(|headers: HeaderMap, Json(body): Json<Value>| async move {
+                assert_eq!(headers["authorization"], "Bearer sk-test");
+                assert_eq!(body["model"], "mock-model");
+                assert_eq!(body["max_tokens"], 64);
+                assert_eq!(body["messages"], json!([{ "role": "user", "content": "2+2?" }]));
+                Json(completion(" 4 \n"))
+            }),
+        );
+        let base_url = mock_server(router).await;
+        let registry = registry(json!({
+            "mock": { "openai": {
+                "base_url": base_url,
+                "model": "mock-model",
+                "api_key_env": "MCP_COUNCIL_TEST_KEY",
+                "max_tokens": 64
+            } }
+        }));
+
+        let answer = run_llm(&registry, "mock", "2+2?").await.unwrap();
+        assert_eq!(answer.text, "4");
+        assert_eq!(answer.engine, "mock");
+        assert_eq!(
+            answer.usage,
+            Some(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 5,
+                total_tokens: 17
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn retryable_statuses_are_retried() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let router = Router::new().route(
+            "/v1/chat/completions",
+            post(move || async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    (StatusCode::SERVICE_UNAVAILABLE, "model is loading").into_response()
+                } else {
+                    Json(completion("ready")).into_response()
+                }
+            }),
+        );
+        let base_url = mock_server(router).await;
+        let registry = registry(json!({
+            "mock": {
+                "openai": { "base_url": base_url, "model": "m" },
+                "retry": { "max_attempts": 2, "backoff_ms": 10, "retry_exit_codes": [503] }
+            }
+        }));
+
+        let answer = run_llm(&registry, "mock", "hi").await.unwrap();
+        assert_eq!(answer.text, "ready");
+        assert_eq!(answer.attempts, 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn client_errors_go_straight_to_the_fallback() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let rejecting = Router::new().route(
+            "/v1/chat/completions",
+            post(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                (StatusCode::UNAUTHORIZED, "invalid api key")
+            }),
+        );
+        let answering = Router::new().route(
+            "/v1/chat/completions",
+            post(|| async { Json(completion("from fallback")) }),
+        );
+        let registry = registry(json!({
+            "mock": {
+                "openai": { "base_url": mock_server(rejecting).await, "model": "m" },
+                "retry": { "max_attempts": 3, "backoff_ms": 1 },
+                "fallback": ["spare"]
+            },
+            "spare": { "openai": { "base_url": mock_server(answering).await, "model": "m" } }
+        }));
+
+        let answer = run_llm(&registry, "mock", "hi").await.unwrap();
+        assert_eq!(answer.text, "from fallback");
+        assert_eq!(answer.engine, "spare");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn oversized_responses_fail_without_retrying() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let router = Router::new().route(
+            "/v1/chat/completions",
+            post(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Json(completion(&"x".repeat(10_000)))
+            }),
+        );
+        let base_url = mock_server(router).await;
+        let registry = registry(json!({
+            "mock": {
+                "openai": { "base_url": base_url, "model": "m" },
+                "max_output_bytes": 1000,
+                "retry": { "max_attempts": 3, "backoff_ms": 1 }
+            }
+        }));
+
+        let error = run_llm(&registry, "mock", "hi").await.unwrap_err();
+        assert!(
+            matches!(
+                error.downcast_ref::<EngineError>(),
+                Some(EngineError::ResponseTooLarge { limit: 1000, .. })
+            ),
+            "expected ResponseTooLarge, got {:#}",
+            error
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn errors_are_typed() {
+        let router = Router::new()
+            .route(
+                "/bad/v1/chat/completions",
+                post(|| async { (StatusCode::BAD_REQUEST, "unknown model") }),
+            )
+            .route(
+                "/slow/v1/chat/completions",
+                post(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Json(completion("too late"))
+                }),
+            );
+        let base_url = mock_server(router).await.replace("/v1/", "");
+        let registry = registry(json!({
+            "bad": { "openai": { "base_url": format!("{}/bad/v1", base_url), "model": "m" } },
+            "slow": {
+                "openai": { "base_url": format!("{}/slow/v1", base_url), "model": "m" },
+                "timeout_secs": 1
+            }
+        }));
+
+        let error = run_llm(&registry, "bad", "hi").await.unwrap_err();
+        match error.downcast_ref::<EngineError>() {
+            Some(EngineError::HttpStatus {
+                status, body_tail, ..
+            }) => {
+                assert_eq!(*status, 400);
+                assert_eq!(body_tail, "unknown model");
+            }
+            other => panic!("expected HttpStatus, got {:?}", other),
+        }
+
+        let error = run_llm(&registry, "slow", "hi").await.unwrap_err();
+        assert!(
+            matches!(
+                error.downcast_ref::<EngineError>(),
+                Some(EngineError::Timeout { timeout_secs: 1, .. })
+            ),
+            "expected Timeout, got {:#}",
+            error
+        );
+    }
+}
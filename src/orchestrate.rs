@@ -0,0 +1,326 @@
+use crate::cli_runner::{run_llm, EngineAnswer};
+use crate::council_dir::find_council_dir;
+use crate::engines::EngineRegistry;
+use crate::manifest::Manifest;
+use crate::progress::Progress;
+use crate::slug::validate_slug;
+use crate::tools::finalize::handle_finalize;
+use crate::tools::first_answer::handle_first_answer;
+use crate::tools::peer_review::handle_peer_review;
+use crate::tools::save_final::handle_save_final;
+use crate::tools::save_review::handle_save_review;
+use crate::tools::tally::handle_tally;
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+pub const RUN_USAGE: &str =
+    "mcp-council run --title <slug> --models <a,b,c> [--chair <model>] \"question\"";
+
+/// Arguments of `mcp-council run`.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub title: String,
+    /// Engine names; each one answers and reviews
+    pub models: Vec<String>,
+    /// Engine that writes the final answer (default: the first model)
+    pub chair: String,
+    pub question: String,
+}
+
+impl RunOptions {
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut title = None;
+        let mut models = None;
+        let mut chair = None;
+        let mut question = Vec::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .cloned()
+                    .context(format!("{} needs a value\nUsage: {}", name, RUN_USAGE))
+            };
+            match arg.as_str() {
+                "--title" => title = Some(value("--title")?),
+                "--models" => models = Some(value("--models")?),
+                "--chair" => chair = Some(value("--chair")?),
+                flag if flag.starts_with("--") => {
+                    anyhow::bail!("Unknown option {}\nUsage: {}", flag, RUN_USAGE)
+                }
+                word => question.push(word.to_string()),
+            }
+        }
+
+        let title = title.context(format!("--title is required\nUsage: {}", RUN_USAGE))?;
+        validate_slug(&title)?;
+        let models: Vec<String> = models
+            .context(format!("--models is required\nUsage: {}", RUN_USAGE))?
+            .split(',')
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect();
+        if models.len() < 2 {
+            anyhow::bail!("A council needs at least two models to review each other");
+        }
+        let chair = chair.unwrap_or_else(|| models[0].clone());
+        let question = question.join(" ");
+        if question.trim().is_empty() {
+            anyhow::bail!("The question is missing\nUsage: {}", RUN_USAGE);
+        }
+
+        Ok(Self {
+            title,
+            models,
+            chair,
+            question,
+        })
+    }
+}
+
+/// Ask each `(model, prompt)` pair's engine in parallel. Answers come back
+/// in the order of `jobs`; a model that fails after its retries and
+/// fallbacks fails the whole step.
+async fn ask_all(registry: &Arc<EngineRegistry>, jobs: Vec<(String, String)>) -> Result<Vec<EngineAnswer>> {
+    let mut tasks = JoinSet::new();
+    for (idx, (model, prompt)) in jobs.into_iter().enumerate() {
+        let registry = Arc::clone(registry);
+        tasks.spawn(async move {
+            let answer = run_llm(&registry, &model, &prompt)
+                .await
+                .context(format!("{} failed", model));
+            if let Ok(answer) = &answer {
+                log_info!("{} answered (via {})", model, answer.engine);
+            }
+            (idx, answer)
+        });
+    }
+
+    let mut answers = vec![None; tasks.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (idx, answer) = joined.context("Engine task panicked")?;
+        answers[idx] = Some(answer?);
+    }
+    Ok(answers.into_iter().flatten().collect())
+}
+
+/// Note in the session manifest which engine answered for each model.
+async fn record_runs(title: &str, stage: &str, runs: &[(&String, &EngineAnswer)]) -> Result<()> {
+    let base_dir = find_council_dir()?.join(title);
+    let _lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+    for (model, answer) in runs {
+        manifest.record_engine_run(stage, model, &answer.engine, answer.attempts, answer.usage);
+    }
+    manifest.save(&base_dir)
+}
+
+/// Run all three stages through the engines' CLIs, writing the same files
+/// the MCP tools write. Model calls within a stage run in parallel; results
+/// are saved one at a time so the session manifest sees every update.
+/// Returns the final answer file.
+pub async fn run_council(options: &RunOptions) -> Result<PathBuf> {
+    let registry = Arc::new(EngineRegistry::load()?);
+    for model in options.models.iter().chain([&options.chair]) {
+        for fallback in &registry.get(model)?.fallback {
+            registry
+                .get(fallback)
+                .context(format!("Fallback of {}", model))?;
+        }
+    }
+    let title = options.title.as_str();
+    let progress = Progress::none();
+
+    log_info!("Stage1: asking {}", options.models.join(", "));
+    let jobs = options
+        .models
+        .iter()
+        .map(|model| (model.clone(), options.question.clone()))
+        .collect();
+    let answers = ask_all(&registry, jobs).await?;
+    for (model, answer) in options.models.iter().zip(&answers) {
+        handle_first_answer(json!({
+            "title": title,
+            "model": model,
+            "prompt": options.question,
+            "content": answer.text
+        }))
+        .await?;
+    }
+    record_runs(title, "stage1", &options.models.iter().zip(&answers).collect::<Vec<_>>()).await?;
+
+    log_info!("Stage2: peer review");
+    let mut jobs = Vec::new();
+    for model in &options.models {
+        let request = handle_peer_review(
+            json!({ "title": title, "model": model, "self_model": model, "use_sampling": false }),
+            None,
+            &progress,
+        )
+        .await?;
+        let prompt = request["review_request"]
+            .as_str()
+            .context("peer_review returned no review_request")?;
+        jobs.push((model.clone(), prompt.to_string()));
+    }
+    let reviews = ask_all(&registry, jobs).await?;
+    for (model, review) in options.models.iter().zip(&reviews) {
+        let saved = handle_save_review(json!({
+            "title": title,
+            "model": model,
+            "content": review.text
+        }))
+        .await?;
+        if let Some(warning) = saved["ranking_warning"].as_str() {
+            log_warn!("Review by {}: {}", model, warning);
+        }
+    }
+    record_runs(title, "stage2", &options.models.iter().zip(&reviews).collect::<Vec<_>>()).await?;
+
+    let tally = handle_tally(json!({ "title": title }), &progress).await?;
+    log_info!("Tally: {}", tally["summary"].as_str().unwrap_or_default());
+
+    log_info!("Stage3: {} synthesizes the final answer", options.chair);
+    let request = handle_finalize(
+        json!({ "title": title, "model": options.chair, "use_sampling": false }),
+        None,
+        &progress,
+    )
+    .await?;
+    let prompt = request["data"]["chairman_prompt"]
+        .as_str()
+        .context("finalize returned no chairman_prompt")?
+        .to_string();
+    let final_answer = ask_all(&registry, vec![(options.chair.clone(), prompt)])
+        .await?
+        .remove(0);
+    let saved = handle_save_final(json!({
+        "title": title,
+        "model": options.chair,
+        "content": final_answer.text
+    }))
+    .await?;
+    record_runs(title, "stage3", &[(&options.chair, &final_answer)]).await?;
+
+    let path = saved["file_saved"]
+        .as_str()
+        .context("save_final returned no file")?;
+    Ok(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_ENV;
+    use serde_json::Value;
+    use std::env;
+    use std::fs;
+
+    fn parse(args: &str) -> Result<RunOptions> {
+        let args: Vec<String> = args.split_whitespace().map(|a| a.to_string()).collect();
+        RunOptions::parse(&args)
+    }
+
+    fn parse_error(args: &str) -> String {
+        parse(args).unwrap_err().to_string()
+    }
+
+    /// A stub engine that answers, reviews or synthesizes depending on the
+    /// prompt it reads on stdin.
+    fn stub_engine(name: &str) -> Value {
+        let script = format!(
+            r#"prompt=$(cat)
+case "$prompt" in
+  *Chairman*) echo "final from {name}" ;;
+  *"FINAL RANKING:"*) printf 'Solid.\n\nFINAL RANKING:\n1. Response A\n' ;;
+  *) echo "{name} answers" ;;
+esac"#
+        );
+        json!({ "command": "sh", "args": ["-c", script] })
+    }
+
+    #[test]
+    fn options_need_a_title_models_and_a_question() {
+        let error = parse_error("--models a,b why?");
+        assert!(error.starts_with("--title is required"), "{}", error);
+        let error = parse_error("--title t why?");
+        assert!(error.starts_with("--models is required"), "{}", error);
+        let error = parse_error("--title t --models a,b");
+        assert!(error.starts_with("The question is missing"), "{}", error);
+        let error = parse_error("--title t --models a why?");
+        assert_eq!(error, "A council needs at least two models to review each other");
+        let error = parse_error("--title t --models a, why?");
+        assert_eq!(error, "A council needs at least two models to review each other");
+        let error = parse_error("--title t --models a,b --temperature 1 why?");
+        assert!(error.starts_with("Unknown option --temperature"), "{}", error);
+        let error = parse_error("--title t --models");
+        assert!(error.starts_with("--models needs a value"), "{}", error);
+        assert!(parse("--title ../t --models a,b why?").is_err());
+    }
+
+    #[test]
+    fn the_chair_defaults_to_the_first_model() {
+        let options = parse("--title t --models gpt,claude why is the sky blue?").unwrap();
+        assert_eq!(options.title, "t");
+        assert_eq!(options.models, ["gpt", "claude"]);
+        assert_eq!(options.chair, "gpt");
+        assert_eq!(options.question, "why is the sky blue?");
+
+        let options = parse("why? --chair gemini --title t --models gpt,claude").unwrap();
+        assert_eq!(options.chair, "gemini");
+        assert_eq!(options.question, "why?");
+    }
+
+    #[tokio::test]
+    async fn run_council_writes_every_stage() {
+        crate::conformance::council_root();
+        let config = json!({
+            "engines": {
+                "alpha": stub_engine("alpha"),
+                "beta": { "command": "sh", "args": ["-c", "exit 3"], "fallback": ["gamma"] },
+                "gamma": stub_engine("gamma")
+            }
+        });
+        // Replaced in one step; other tests read the config concurrently
+        let config_path = PathBuf::from(env::var(CONFIG_ENV).unwrap());
+        let tmp = config_path.with_extension("tmp");
+        fs::write(&tmp, config.to_string()).unwrap();
+        fs::rename(&tmp, &config_path).unwrap();
+
+        let title = "orchestrate-run";
+        let base_dir = find_council_dir().unwrap().join(title);
+        let _ = fs::remove_dir_all(&base_dir);
+        let options = parse(&format!("--title {} --models alpha,beta why?", title)).unwrap();
+
+        let final_path = run_council(&options).await.unwrap();
+        assert_eq!(final_path, base_dir.join("final-answer-by-alpha.md"));
+        let final_answer = fs::read_to_string(&final_path).unwrap();
+        assert!(final_answer.ends_with("final from alpha"), "{}", final_answer);
+        assert!(final_answer.contains("peer-review-by-beta.md"), "{}", final_answer);
+
+        let manifest = Manifest::load(&base_dir).unwrap().unwrap();
+        assert_eq!(manifest.query(), "why?");
+        let runs: Vec<(&str, &str, &str, u32)> = manifest
+            .engine_runs
+            .iter()
+            .map(|r| (r.stage.as_str(), r.model.as_str(), r.engine.as_str(), r.attempts))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("stage1", "alpha", "alpha", 1),
+                ("stage1", "beta", "gamma", 1),
+                ("stage2", "alpha", "alpha", 1),
+                ("stage2", "beta", "gamma", 1),
+                ("stage3", "alpha", "alpha", 1),
+            ]
+        );
+        assert!(manifest.engine_runs.iter().all(|r| r.usage.is_none()));
+        // Answers keep the model they were asked for
+        let answered: Vec<&str> = manifest.answers.iter().map(|a| a.model.as_str()).collect();
+        assert_eq!(answered, ["alpha", "beta"]);
+    }
+}
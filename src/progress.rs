@@ -0,0 +1,60 @@
+use crate::client::ClientPeer;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Sends `notifications/progress` for one `tools/call` that carried a
+/// `_meta.progressToken`. Without a token every report is a no-op, so
+/// handlers can report unconditionally.
+pub struct Progress {
+    sink: Option<(Arc<ClientPeer>, Value)>,
+    done: AtomicU64,
+    total: AtomicU64,
+}
+
+impl Progress {
+    pub fn new(client: Arc<ClientPeer>, token: Option<Value>) -> Self {
+        let sink = token
+            .filter(|t| t.is_string() || t.is_number())
+            .map(|token| (client, token));
+        Self {
+            sink,
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+        }
+    }
+
+    /// Progress that is never reported, for handlers run outside MCP.
+    pub fn none() -> Self {
+        Self {
+            sink: None,
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the expected number of steps. Zero means unknown.
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Mark one more step done and tell the client what just happened.
+    pub fn step(&self, message: impl AsRef<str>) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let Some((client, token)) = &self.sink else {
+            return;
+        };
+        let total = self.total.load(Ordering::Relaxed);
+        let mut params = json!({
+            "progressToken": token,
+            "progress": done,
+            "message": message.as_ref()
+        });
+        if total >= done {
+            params["total"] = json!(total);
+        }
+        if let Err(e) = client.notify("notifications/progress", params) {
+            log_warn!("Failed to send progress notification: {}", e);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::commands::COMMANDS;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+/// A `prompts/get` request that names an unknown prompt or omits a
+/// required argument. Surfaced to clients as a JSON-RPC invalid-params error.
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("Unknown prompt: {0}")]
+    UnknownPrompt(String),
+    #[error("Missing required argument '{argument}' for prompt '{prompt}'")]
+    MissingArgument { prompt: String, argument: String },
+}
+
+impl PromptError {
+    pub fn to_error_data(&self) -> Value {
+        match self {
+            PromptError::UnknownPrompt(name) => json!({ "param": "name", "value": name }),
+            PromptError::MissingArgument { prompt, argument } => {
+                json!({ "prompt": prompt, "param": argument })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PromptArgument {
+    name: String,
+    required: bool,
+}
+
+/// A slash-command file split into its frontmatter fields and body.
+#[derive(Debug, Default)]
+struct CommandPrompt {
+    name: String,
+    description: String,
+    arguments: Vec<PromptArgument>,
+    body: String,
+}
+
+/// Argument descriptions shared by every command; the frontmatter only
+/// carries type and `required`.
+fn argument_description(name: &str) -> &'static str {
+    match name {
+        "title" => "Council slug (directory name), e.g. \"coloree-review\"",
+        "model" => "Model running the command, e.g. \"gpt-5\"",
+        "self_model" => "Model whose own answer is excluded from review",
+        "engine" => "CLI engine used to run the chairman model",
+        "content" => "Markdown content to save",
+        "max_length" => "Maximum summary length in characters (default 2000)",
+        _ => "",
+    }
+}
+
+/// Parse the YAML-style frontmatter used by `commands/cc/*.md`. Only the
+/// shapes those files use are understood: `name:`, a folded `description: >`
+/// block, and an `inputs:` map of `{type, required}` entries.
+fn parse_command(content: &str) -> Option<CommandPrompt> {
+    let rest = content.strip_prefix("---\n")?;
+    let (front, body) = rest.split_once("\n---\n")?;
+
+    let mut prompt = CommandPrompt {
+        body: body.trim().to_string(),
+        ..Default::default()
+    };
+    let mut section = "";
+    for line in front.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if indent == 0 {
+            let (key, value) = line.split_once(':')?;
+            section = key;
+            match (key, value.trim()) {
+                ("name", value) => prompt.name = value.to_string(),
+                ("description", value) if value != ">" => prompt.description = value.to_string(),
+                _ => {}
+            }
+            continue;
+        }
+
+        match section {
+            "description" => {
+                if !prompt.description.is_empty() {
+                    prompt.description.push(' ');
+                }
+                prompt.description.push_str(line);
+            }
+            "inputs" if indent == 2 => prompt.arguments.push(PromptArgument {
+                name: line.trim_end_matches(':').to_string(),
+                required: false,
+            }),
+            "inputs" => {
+                if let (Some(arg), Some(("required", value))) =
+                    (prompt.arguments.last_mut(), line.split_once(':').map(|(k, v)| (k, v.trim())))
+                {
+                    arg.required = value == "true";
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(prompt).filter(|p| !p.name.is_empty())
+}
+
+fn command_prompts() -> Vec<CommandPrompt> {
+    COMMANDS
+        .iter()
+        .filter_map(|(file, content)| {
+            let prompt = parse_command(content);
+            if prompt.is_none() {
+                log_warn!("Skipping command {} with unreadable frontmatter", file);
+            }
+            prompt
+        })
+        .collect()
+}
+
+pub fn list_prompts() -> Value {
+    let prompts: Vec<Value> = command_prompts()
+        .iter()
+        .map(|p| {
+            json!({
+                "name": p.name,
+                "description": p.description,
+                "arguments": p.arguments.iter().map(|a| json!({
+                    "name": a.name,
+                    "description": argument_description(&a.name),
+                    "required": a.required
+                })).collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    json!({ "prompts": prompts })
+}
+
+/// Render a command as a single user message: the command body followed by
+/// the arguments the client supplied.
+pub fn get_prompt(name: &str, arguments: &Map<String, Value>) -> Result<Value> {
+    let prompt = command_prompts()
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| PromptError::UnknownPrompt(name.to_string()))?;
+
+    let mut provided = Vec::new();
+    for arg in &prompt.arguments {
+        let value = arguments.get(&arg.name).and_then(|v| match v {
+            Value::String(s) if s.trim().is_empty() => None,
+            Value::String(s) => Some(s.clone()),
+            Value::Null => None,
+            other => Some(other.to_string()),
+        });
+        match value {
+            Some(value) => provided.push(format!("- `{}`: {}", arg.name, value)),
+            None if arg.required => {
+                return Err(PromptError::MissingArgument {
+                    prompt: prompt.name.clone(),
+                    argument: arg.name.clone(),
+                }
+                .into())
+            }
+            None => {}
+        }
+    }
+
+    let mut text = prompt.body.clone();
+    if !provided.is_empty() {
+        text.push_str("\n\nArguments:\n");
+        text.push_str(&provided.join("\n"));
+    }
+
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [
+            {
+                "role": "user",
+                "content": {
+                    "type": "text",
+                    "text": text
+                }
+            }
+        ]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontmatter_is_parsed() {
+        let content = "---\nname: demo\nversion: 0.1.0\ndescription: >\n  First line.\n  Second line.\ninputs:\n  title:\n    type: string\n    required: true\n  model:\n    type: string\n    required: false\n  extra:\n    type: string\n---\n\nDo the thing.\n";
+        let prompt = parse_command(content).unwrap();
+        assert_eq!(prompt.name, "demo");
+        assert_eq!(prompt.description, "First line. Second line.");
+        let arguments: Vec<(&str, bool)> = prompt
+            .arguments
+            .iter()
+            .map(|a| (a.name.as_str(), a.required))
+            .collect();
+        assert_eq!(arguments, [("title", true), ("model", false), ("extra", false)]);
+        assert_eq!(prompt.body, "Do the thing.");
+
+        let inline = parse_command("---\nname: short\ndescription: One line\n---\nBody\n").unwrap();
+        assert_eq!(inline.description, "One line");
+        assert!(inline.arguments.is_empty());
+    }
+
+    #[test]
+    fn files_without_frontmatter_are_skipped() {
+        assert!(parse_command("# Just markdown\n\nNo frontmatter here.\n").is_none());
+        assert!(parse_command("").is_none());
+    }
+
+    #[test]
+    fn malformed_frontmatter_is_rejected() {
+        // Never closed
+        assert!(parse_command("---\nname: open\n\nBody\n").is_none());
+        // A top-level line that is not `key: value`
+        assert!(parse_command("---\nname: bad\njust words\n---\nBody\n").is_none());
+        // No name
+        assert!(parse_command("---\ndescription: nameless\n---\nBody\n").is_none());
+    }
+
+    #[test]
+    fn every_command_is_listed_with_its_required_arguments() {
+        let expected: &[(&str, &[&str])] = &[
+            ("finalize.md", &["title"]),
+            ("first_answer.md", &["title"]),
+            ("peer_review.md", &["title"]),
+            ("save_final.md", &["title", "content"]),
+            ("save_review.md", &["title", "content"]),
+            ("save_summary.md", &["title", "content"]),
+            ("status.md", &["title"]),
+            ("summarize.md", &["title", "content"]),
+            ("tally.md", &["title"]),
+        ];
+        let listed = list_prompts();
+        let prompts = listed["prompts"].as_array().unwrap();
+        assert_eq!(prompts.len(), COMMANDS.len());
+
+        for (file, _) in COMMANDS {
+            let name = file.trim_end_matches(".md");
+            let prompt = prompts
+                .iter()
+                .find(|p| p["name"] == name)
+                .unwrap_or_else(|| panic!("{} is not listed", file));
+            assert!(!prompt["description"].as_str().unwrap().is_empty(), "{}", file);
+
+            let arguments = prompt["arguments"].as_array().unwrap();
+            let required: Vec<&str> = arguments
+                .iter()
+                .filter(|a| a["required"] == true)
+                .map(|a| a["name"].as_str().unwrap())
+                .collect();
+            let (_, want) = expected
+                .iter()
+                .find(|(f, _)| f == file)
+                .unwrap_or_else(|| panic!("no expectation for {}", file));
+            assert_eq!(required, *want, "{}", file);
+            assert!(
+                arguments.iter().all(|a| !a["description"].as_str().unwrap().is_empty()),
+                "{}: {}",
+                file,
+                prompt
+            );
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Header that `council.peer_review` asks reviewers to put before their ranking.
+const RANKING_HEADER: &str = "FINAL RANKING:";
+
+/// Suffix of the structured ranking saved next to `peer-review-by-{model}.md`.
+pub const RANKING_SUFFIX: &str = ".ranking.json";
+
+/// Suffix of the label mapping written by `council.peer_review` for each reviewer.
+pub const LABELS_SUFFIX: &str = ".labels.json";
+
+/// Which Stage1 answer each anonymized label pointed at when the review was requested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelAssignment {
+    pub label: String,
+    pub model: String,
+    pub file: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelMapping {
+    pub title: String,
+    pub reviewer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_model: Option<String>,
+    pub created_at: String,
+    pub labels: Vec<LabelAssignment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankingEntry {
+    pub rank: usize,
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewRanking {
+    pub title: String,
+    pub reviewer: String,
+    pub review_file: String,
+    pub created_at: String,
+    pub ranking: Vec<RankingEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Extract the ordered "Response X" labels from the last FINAL RANKING section.
+///
+/// Returns `None` when the review has no ranking section or the section lists
+/// no recognizable labels.
+pub fn parse_final_ranking(review: &str) -> Option<Vec<String>> {
+    let upper = review.to_ascii_uppercase();
+    let start = upper.rfind(RANKING_HEADER)? + RANKING_HEADER.len();
+    let section = &review[start..];
+
+    let mut labels: Vec<String> = Vec::new();
+    for line in section.lines() {
+        let line = line.trim().trim_matches('*').trim();
+        if line.is_empty() {
+            continue;
+        }
+        // "1. Response C 2. Response A" on one line holds several items
+        let found: Vec<String> = list_items(line)
+            .into_iter()
+            .filter_map(find_response_label)
+            .collect();
+        if found.is_empty() {
+            // Prose after the list ends the ranking section
+            if !labels.is_empty() {
+                break;
+            }
+            continue;
+        }
+        for label in found {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+
+    if labels.is_empty() {
+        None
+    } else {
+        Some(labels)
+    }
+}
+
+/// Split a line at each numbered marker ("1.", "2)") that starts a word, so
+/// an inline ranking yields one item per response.
+fn list_items(line: &str) -> Vec<&str> {
+    let bytes = line.as_bytes();
+    let mut starts = vec![0];
+    for i in 1..bytes.len() {
+        if !bytes[i - 1].is_ascii_whitespace() || !bytes[i].is_ascii_digit() {
+            continue;
+        }
+        let digits = bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if matches!(bytes.get(i + digits), Some(b'.') | Some(b')')) {
+            starts.push(i);
+        }
+    }
+    starts.push(line.len());
+    starts.windows(2).map(|w| line[w[0]..w[1]].trim()).collect()
+}
+
+/// Parse a ranking list item ("1. Response A", "2) **Response B**", "- Response C")
+/// into a normalized "Response X" label.
+///
+/// A line without a list marker only counts when it holds nothing but the
+/// label, so prose such as "Response A is the most thorough" is skipped.
+fn find_response_label(line: &str) -> Option<String> {
+    let unmarked = line
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['.', ')', '-', '*', ':']);
+    let marked = unmarked.len() < line.len();
+    let item = unmarked.trim_start().trim_start_matches(['*', '_', '`']);
+    let upper = item.to_ascii_uppercase();
+    let rest = upper.strip_prefix("RESPONSE")?;
+    let mut chars = rest.trim_start().chars();
+    let letter = chars.next().filter(|c| c.is_ascii_uppercase())?;
+    let tail = chars.as_str();
+    // Reject words such as "Responses" or "Response Alpha"
+    if tail.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    if !marked && tail.contains(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(format!("Response {}", letter))
+}
+
+/// Resolve parsed labels through the reviewer's label mapping into a
+/// `ReviewRanking`. Returns `None` when the review has no ranking section.
+pub fn build_review_ranking(
+    title: &str,
+    reviewer: &str,
+    review_path: &Path,
+    review: &str,
+) -> Option<ReviewRanking> {
+    let labels = parse_final_ranking(review)?;
+    let mapping = load_label_mapping(review_path);
+    let base_dir = review_path.parent().unwrap_or(Path::new("."));
+
+    let mut warnings = Vec::new();
+    if mapping.is_none() {
+        warnings.push(format!(
+            "No label mapping found at {}; run council.peer_review before saving the review",
+            labels_path_for(review_path).display()
+        ));
+    }
+
+    let ranking = labels
+        .into_iter()
+        .enumerate()
+        .map(|(idx, label)| {
+            let assignment = mapping
+                .as_ref()
+                .and_then(|m| m.labels.iter().find(|a| a.label == label));
+            match assignment {
+                Some(a) => {
+                    let current = fs::read(base_dir.join(&a.file)).ok().map(|b| sha256_hex(&b));
+                    if current.as_deref() != Some(a.sha256.as_str()) {
+                        warnings.push(format!(
+                            "{} ({}) changed or was removed after the review was requested",
+                            label, a.file
+                        ));
+                    }
+                }
+                None if mapping.is_some() => {
+                    warnings.push(format!("{} does not match any reviewed answer", label));
+                }
+                None => {}
+            }
+            RankingEntry {
+                rank: idx + 1,
+                model: assignment.map(|a| a.model.clone()),
+                file: assignment.map(|a| a.file.clone()),
+                label,
+            }
+        })
+        .collect();
+
+    Some(ReviewRanking {
+        title: title.to_string(),
+        reviewer: reviewer.to_string(),
+        review_file: review_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        created_at: Utc::now().to_rfc3339(),
+        ranking,
+        warnings,
+    })
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn sidecar_path(review_path: &Path, suffix: &str) -> PathBuf {
+    let stem = review_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    review_path.with_file_name(format!("{}{}", stem, suffix))
+}
+
+pub fn ranking_path_for(review_path: &Path) -> PathBuf {
+    sidecar_path(review_path, RANKING_SUFFIX)
+}
+
+pub fn labels_path_for(review_path: &Path) -> PathBuf {
+    sidecar_path(review_path, LABELS_SUFFIX)
+}
+
+/// True for files written next to a review (rankings, label mappings), which
+/// must not be mistaken for reviews themselves.
+pub fn is_sidecar(file_name: &str) -> bool {
+    file_name.ends_with(RANKING_SUFFIX) || file_name.ends_with(LABELS_SUFFIX)
+}
+
+pub fn save_label_mapping(review_path: &Path, mapping: &LabelMapping) -> Result<PathBuf> {
+    let path = labels_path_for(review_path);
+    let json = serde_json::to_string_pretty(mapping)?;
+    fs::write(&path, json)
+        .context(format!("Failed to write label mapping file: {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn load_label_mapping(review_path: &Path) -> Option<LabelMapping> {
+    let content = fs::read_to_string(labels_path_for(review_path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save_ranking(review_path: &Path, ranking: &ReviewRanking) -> Result<PathBuf> {
+    let path = ranking_path_for(review_path);
+    let json = serde_json::to_string_pretty(ranking)?;
+    fs::write(&path, json)
+        .context(format!("Failed to write ranking file: {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn load_ranking(review_path: &Path) -> Option<ReviewRanking> {
+    let content = fs::read_to_string(ranking_path_for(review_path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mcp-council-ranking-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn expected(letters: &[&str]) -> Option<Vec<String>> {
+        Some(letters.iter().map(|l| format!("Response {}", l)).collect())
+    }
+
+    /// Write answer files and the label mapping peer_review would save for `reviewer`.
+    fn labeled_session(dir: &Path, reviewer: &str, answers: &[(&str, &str)]) -> PathBuf {
+        let mut assignments = Vec::new();
+        for (idx, (model, content)) in answers.iter().enumerate() {
+            let file = format!("{}-answer.md", model);
+            fs::write(dir.join(&file), content).unwrap();
+            assignments.push(LabelAssignment {
+                label: format!("Response {}", (b'A' + idx as u8) as char),
+                model: model.to_string(),
+                file,
+                sha256: sha256_hex(content.as_bytes()),
+            });
+        }
+        let review_path = dir.join(format!("peer-review-by-{}.md", reviewer));
+        let mapping = LabelMapping {
+            title: "t".to_string(),
+            reviewer: reviewer.to_string(),
+            self_model: None,
+            created_at: Utc::now().to_rfc3339(),
+            labels: assignments,
+        };
+        save_label_mapping(&review_path, &mapping).unwrap();
+        review_path
+    }
+
+    #[test]
+    fn numbered_and_bulleted_lists_parse() {
+        let numbered = "Analysis...\n\nFINAL RANKING:\n1. Response C\n2) **Response A**\n3. Response B\n";
+        assert_eq!(parse_final_ranking(numbered), expected(&["C", "A", "B"]));
+
+        let bulleted = "FINAL RANKING:\n- Response A\n- Response C\n* Response B\n";
+        assert_eq!(parse_final_ranking(bulleted), expected(&["A", "C", "B"]));
+    }
+
+    #[test]
+    fn inline_rankings_yield_every_label() {
+        assert_eq!(
+            parse_final_ranking("FINAL RANKING: 1. Response C 2. Response A"),
+            expected(&["C", "A"])
+        );
+        assert_eq!(
+            parse_final_ranking("Final ranking: 1) Response B 2) Response C 3) Response A"),
+            expected(&["B", "C", "A"])
+        );
+    }
+
+    #[test]
+    fn prose_before_the_list_is_not_ranked() {
+        let review = "FINAL RANKING:\nResponse A is the most thorough, but it misses the edge case.\n\n1. Response B\n2. Response A\n";
+        assert_eq!(parse_final_ranking(review), expected(&["B", "A"]));
+
+        let review = "FINAL RANKING:\nAfter weighing them, Response A edges out the others:\n1. Response A\n2. Response C\n";
+        assert_eq!(parse_final_ranking(review), expected(&["A", "C"]));
+    }
+
+    #[test]
+    fn prose_after_the_list_ends_it() {
+        let review = "FINAL RANKING:\n1. Response B\n2. Response A\n\nNote that I would rank Response C last.\n- Response C\n";
+        assert_eq!(parse_final_ranking(review), expected(&["B", "A"]));
+    }
+
+    #[test]
+    fn the_last_ranking_section_wins_and_duplicates_collapse() {
+        let review = "FINAL RANKING:\n1. Response A\n\nOn reflection:\nFINAL RANKING:\n1. Response B\n2. Response B\n3. Response A\n";
+        assert_eq!(parse_final_ranking(review), expected(&["B", "A"]));
+        assert_eq!(parse_final_ranking("No ranking here, Response A is best."), None);
+        assert_eq!(parse_final_ranking("FINAL RANKING:\nI cannot decide."), None);
+    }
+
+    #[test]
+    fn unassigned_labels_are_reported() {
+        let dir = scratch("unassigned");
+        let review_path = labeled_session(&dir, "gemini", &[("claude", "a"), ("gpt", "b")]);
+
+        let review = "FINAL RANKING:\n1. Response B\n2. Response D\n3. Response A\n";
+        let ranking = build_review_ranking("t", "gemini", &review_path, review).unwrap();
+
+        let models: Vec<Option<&str>> = ranking.ranking.iter().map(|e| e.model.as_deref()).collect();
+        assert_eq!(models, vec![Some("gpt"), None, Some("claude")]);
+        assert_eq!(ranking.ranking[1].rank, 2);
+        assert_eq!(ranking.warnings, vec!["Response D does not match any reviewed answer".to_string()]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn labels_resolve_through_the_mapping() {
+        let dir = scratch("mapping");
+        let review_path = labeled_session(&dir, "gemini", &[("claude", "a"), ("gpt", "b")]);
+
+        let review = "FINAL RANKING:\n1. Response B\n2. Response A\n";
+        let ranking = build_review_ranking("t", "gemini", &review_path, review).unwrap();
+
+        assert_eq!(ranking.review_file, "peer-review-by-gemini.md");
+        assert!(ranking.warnings.is_empty(), "{:?}", ranking.warnings);
+        let resolved: Vec<(usize, &str, Option<&str>, Option<&str>)> = ranking
+            .ranking
+            .iter()
+            .map(|e| (e.rank, e.label.as_str(), e.model.as_deref(), e.file.as_deref()))
+            .collect();
+        assert_eq!(
+            resolved,
+            vec![
+                (1, "Response B", Some("gpt"), Some("gpt-answer.md")),
+                (2, "Response A", Some("claude"), Some("claude-answer.md")),
+            ]
+        );
+
+        // The saved sidecar round-trips and is not mistaken for a review
+        let saved = save_ranking(&review_path, &ranking).unwrap();
+        assert!(is_sidecar(&saved.file_name().unwrap().to_string_lossy()));
+        assert!(is_sidecar("peer-review-by-gemini.labels.json"));
+        assert!(!is_sidecar("peer-review-by-gemini.md"));
+        assert_eq!(load_ranking(&review_path).unwrap().ranking.len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn answers_changed_after_labeling_are_flagged() {
+        let dir = scratch("changed");
+        let review_path = labeled_session(&dir, "gemini", &[("claude", "a"), ("gpt", "b")]);
+        fs::write(dir.join("claude-answer.md"), "a, edited").unwrap();
+        fs::remove_file(dir.join("gpt-answer.md")).unwrap();
+
+        let review = "FINAL RANKING:\n1. Response A\n2. Response B\n";
+        let ranking = build_review_ranking("t", "gemini", &review_path, review).unwrap();
+
+        // Labels still resolve to what the reviewer was shown
+        assert_eq!(ranking.ranking[0].model.as_deref(), Some("claude"));
+        assert_eq!(
+            ranking.warnings,
+            vec![
+                "Response A (claude-answer.md) changed or was removed after the review was requested".to_string(),
+                "Response B (gpt-answer.md) changed or was removed after the review was requested".to_string(),
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reviews_without_a_mapping_keep_bare_labels() {
+        let dir = scratch("unmapped");
+        let review_path = dir.join("peer-review-by-gemini.md");
+
+        let review = "FINAL RANKING:\n1. Response B\n2. Response A\n";
+        let ranking = build_review_ranking("t", "gemini", &review_path, review).unwrap();
+
+        assert!(ranking.ranking.iter().all(|e| e.model.is_none() && e.file.is_none()));
+        assert_eq!(ranking.ranking[0].label, "Response B");
+        assert_eq!(
+            ranking.warnings,
+            vec![format!(
+                "No label mapping found at {}; run council.peer_review before saving the review",
+                dir.join("peer-review-by-gemini.labels.json").display()
+            )]
+        );
+        assert!(build_review_ranking("t", "gemini", &review_path, "No ranking.").is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
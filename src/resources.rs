@@ -0,0 +1,214 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::slug::validate_slug;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const SCHEME: &str = "council://";
+
+/// A `resources/read` URI that does not map to a file in the council.
+#[derive(Debug, Error)]
+#[error("Resource not found: {uri}")]
+pub struct ResourceNotFound {
+    pub uri: String,
+}
+
+impl ResourceNotFound {
+    pub fn to_error_data(&self) -> Value {
+        json!({ "uri": self.uri })
+    }
+}
+
+/// Resource key for an answer file: `gpt-answer.md` → `gpt`,
+/// `gpt-answer-20250101-101010.md` → `gpt-20250101-101010`.
+fn answer_key(file: &str) -> String {
+    let stem = file.rsplit_once('.').map(|(s, _)| s).unwrap_or(file);
+    stem.replace("-answer", "")
+}
+
+/// Resource key for a review file: `peer-review-by-gpt.md` → `gpt`.
+fn review_key(file: &str) -> String {
+    let stem = file.rsplit_once('.').map(|(s, _)| s).unwrap_or(file);
+    stem.trim_start_matches("peer-review-by-").to_string()
+}
+
+fn mime_type(file: &str) -> &'static str {
+    if file.ends_with(".json") {
+        "application/json"
+    } else {
+        "text/markdown"
+    }
+}
+
+fn resource(uri: String, name: String, description: String, file: &str) -> Value {
+    json!({
+        "uri": uri,
+        "name": name,
+        "description": description,
+        "mimeType": mime_type(file)
+    })
+}
+
+/// The latest saved final answer, if any.
+fn latest_final(manifest: &Manifest) -> Option<&str> {
+    manifest
+        .finals
+        .iter()
+        .max_by(|a, b| a.saved_at.cmp(&b.saved_at))
+        .map(|f| f.file.as_str())
+}
+
+/// Session directories under the council root that hold a valid slug.
+fn sessions(council_base: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut sessions: Vec<(String, PathBuf)> = fs::read_dir(council_base)
+        .context(format!("Failed to read directory: {}", council_base.display()))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            if !path.is_dir() {
+                return None;
+            }
+            let name = path.file_name()?.to_string_lossy().to_string();
+            validate_slug(&name).ok()?;
+            Some((name, path))
+        })
+        .collect();
+    sessions.sort();
+    Ok(sessions)
+}
+
+fn session_resources(slug: &str, base_dir: &Path) -> Result<Vec<Value>> {
+    // Listing and reading never write; sessions without a manifest are
+    // scanned in memory
+    let manifest = Manifest::load_or_scan(base_dir, slug)?;
+    let mut resources = vec![resource(
+        format!("{}{}/manifest", SCHEME, slug),
+        format!("{} manifest", slug),
+        "Session manifest: participants, stage status and file hashes".to_string(),
+        "council.json",
+    )];
+
+    for answer in &manifest.answers {
+        resources.push(resource(
+            format!("{}{}/answers/{}", SCHEME, slug, answer_key(&answer.file)),
+            format!("{} answer by {}", slug, answer.model),
+            format!("Stage1 answer from {} ({})", answer.model, answer.file),
+            &answer.file,
+        ));
+    }
+    for review in manifest.reviews.iter().filter(|r| r.is_saved()) {
+        resources.push(resource(
+            format!("{}{}/reviews/{}", SCHEME, slug, review_key(&review.file)),
+            format!("{} review by {}", slug, review.reviewer),
+            format!("Stage2 peer review by {} ({})", review.reviewer, review.file),
+            &review.file,
+        ));
+    }
+    if let Some(tally) = &manifest.tally {
+        resources.push(resource(
+            format!("{}{}/tally", SCHEME, slug),
+            format!("{} tally", slug),
+            "Aggregated peer rankings".to_string(),
+            &tally.file,
+        ));
+    }
+    if let Some(file) = latest_final(&manifest) {
+        resources.push(resource(
+            format!("{}{}/final", SCHEME, slug),
+            format!("{} final answer", slug),
+            format!("Stage3 final answer ({})", file),
+            file,
+        ));
+    }
+    Ok(resources)
+}
+
+pub fn list_resources() -> Result<Value> {
+    let council_base = find_council_dir()?;
+    let mut resources = Vec::new();
+    for (slug, base_dir) in sessions(&council_base)? {
+        match session_resources(&slug, &base_dir) {
+            Ok(mut session) => resources.append(&mut session),
+            Err(e) => log_warn!("Skipping council '{}' in resources/list: {}", slug, e),
+        }
+    }
+    Ok(json!({ "resources": resources }))
+}
+
+pub fn list_templates() -> Value {
+    let template = |uri: &str, name: &str, description: &str, mime: &str| {
+        json!({
+            "uriTemplate": uri,
+            "name": name,
+            "description": description,
+            "mimeType": mime
+        })
+    };
+    json!({
+        "resourceTemplates": [
+            template("council://{slug}/answers/{model}", "Stage1 answer", "Answer saved by first_answer for a model", "text/markdown"),
+            template("council://{slug}/reviews/{model}", "Stage2 review", "Peer review saved by a reviewer model", "text/markdown"),
+            template("council://{slug}/final", "Stage3 final answer", "Most recent final answer saved by save_final", "text/markdown"),
+            template("council://{slug}/tally", "Peer ranking tally", "Leaderboard written by council.tally", "application/json"),
+            template("council://{slug}/manifest", "Session manifest", "council.json for the session", "application/json")
+        ]
+    })
+}
+
+pub fn read_resource(uri: &str) -> Result<Value> {
+    let not_found = || ResourceNotFound { uri: uri.to_string() };
+    let path = uri.strip_prefix(SCHEME).ok_or_else(not_found)?;
+    let mut parts = path.split('/');
+    let slug = validate_slug(parts.next().unwrap_or_default())?;
+    let kind = parts.next().ok_or_else(not_found)?;
+    let key = parts.next();
+    if parts.next().is_some() {
+        return Err(not_found().into());
+    }
+
+    let council_base = find_council_dir()?;
+    let base_dir = council_base.join(slug);
+    if !base_dir.is_dir() {
+        return Err(not_found().into());
+    }
+    let manifest = Manifest::load_or_scan(&base_dir, slug)?;
+
+    let file = match (kind, key) {
+        ("answers", Some(key)) => manifest
+            .answers
+            .iter()
+            .find(|a| answer_key(&a.file) == key)
+            .map(|a| a.file.clone()),
+        ("reviews", Some(key)) => manifest
+            .reviews
+            .iter()
+            .filter(|r| r.is_saved())
+            .find(|r| review_key(&r.file) == key)
+            .map(|r| r.file.clone()),
+        ("final", None) => latest_final(&manifest).map(|f| f.to_string()),
+        ("tally", None) => manifest.tally.as_ref().map(|t| t.file.clone()),
+        ("manifest", None) => Some(crate::manifest::MANIFEST_FILE.to_string()),
+        _ => None,
+    }
+    .ok_or_else(not_found)?;
+
+    let file_path = base_dir.join(&file);
+    let text = match fs::read_to_string(&file_path) {
+        Ok(text) => text,
+        // Not saved yet: serve the scanned manifest
+        Err(_) if kind == "manifest" => serde_json::to_string_pretty(&manifest)?,
+        Err(_) => return Err(not_found().into()),
+    };
+    Ok(json!({
+        "contents": [
+            {
+                "uri": uri,
+                "mimeType": mime_type(&file),
+                "text": text
+            }
+        ]
+    }))
+}
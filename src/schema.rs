@@ -0,0 +1,167 @@
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// One way a value fails its schema.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// Dotted path of the offending property, e.g. `max_length` or `items.0`
+    pub property: String,
+    pub message: String,
+}
+
+/// `tools/call` arguments that do not match the tool's `inputSchema`.
+///
+/// Surfaced to clients as a JSON-RPC invalid-params error listing every
+/// failing property.
+#[derive(Debug, Error)]
+#[error("Invalid arguments for {tool}: {}", summary(.violations))]
+pub struct InvalidArguments {
+    pub tool: String,
+    pub violations: Vec<Violation>,
+}
+
+fn summary(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|v| format!("{} {}", v.property, v.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl InvalidArguments {
+    pub fn to_error_data(&self) -> Value {
+        json!({
+            "tool": self.tool,
+            "errors": self.violations.iter().map(|v| json!({
+                "property": v.property,
+                "message": v.message
+            })).collect::<Vec<_>>()
+        })
+    }
+}
+
+/// Check `arguments` against a tool's input schema.
+pub fn validate_arguments(tool: &str, schema: &Value, arguments: &Value) -> Result<(), InvalidArguments> {
+    let mut violations = Vec::new();
+    validate(schema, arguments, "", &mut violations);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(InvalidArguments {
+            tool: tool.to_string(),
+            violations,
+        })
+    }
+}
+
+/// The subset of JSON Schema the tool schemas use: `type`, `properties`,
+/// `required`, `additionalProperties: false`, `items`, `enum`, `minimum`
+/// and `maximum`. Unknown keywords are ignored.
+fn validate(schema: &Value, value: &Value, path: &str, violations: &mut Vec<Violation>) {
+    let mut fail = |message: String| {
+        violations.push(Violation {
+            property: if path.is_empty() { "arguments".to_string() } else { path.to_string() },
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            fail(format!("must be {}, got {}", types.join(" or "), type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            fail(format!("must be one of {}", Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+            if n < min {
+                fail(format!("must be at least {}", min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+            if n > max {
+                fail(format!("must be at most {}", max));
+            }
+        }
+    }
+
+    if let Value::Object(object) = value {
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        for required in schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str())
+        {
+            if !object.contains_key(required) {
+                violations.push(Violation {
+                    property: join(path, required),
+                    message: "is required".to_string(),
+                });
+            }
+        }
+        for (key, item) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(property_schema) => validate(property_schema, item, &join(path, key), violations),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    violations.push(Violation {
+                        property: join(path, key),
+                        message: "is not a known property".to_string(),
+                    });
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (idx, item) in items.iter().enumerate() {
+            validate(item_schema, item, &join(path, &idx.to_string()), violations);
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,167 @@
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Longest slug accepted as a council directory name.
+const MAX_SLUG_LEN: usize = 100;
+
+/// A `title` that cannot be used as a council directory name.
+///
+/// Surfaced to clients as a JSON-RPC invalid-params error carrying the
+/// normalized suggestion.
+#[derive(Debug, Error)]
+#[error("Invalid title {title:?}: {reason}{}", suggestion_hint(.suggestion))]
+pub struct InvalidSlug {
+    pub title: String,
+    pub reason: String,
+    pub suggestion: Option<String>,
+}
+
+fn suggestion_hint(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(s) => format!(" (did you mean {:?}?)", s),
+        None => String::new(),
+    }
+}
+
+impl InvalidSlug {
+    pub fn to_error_data(&self) -> Value {
+        json!({
+            "param": "title",
+            "value": self.title,
+            "reason": self.reason,
+            "suggestion": self.suggestion
+        })
+    }
+}
+
+/// Apply the command slug rules: lower-case, spaces and underscores become
+/// "-", anything outside `[a-z0-9-]` is dropped, and runs of "-" collapse.
+pub fn normalize_slug(raw: &str) -> String {
+    let mut slug = String::with_capacity(raw.len());
+    for c in raw.trim().chars().flat_map(|c| c.to_lowercase()) {
+        let c = match c {
+            'a'..='z' | '0'..='9' => c,
+            ' ' | '_' | '-' | '.' | '/' | '\\' => '-',
+            _ => continue,
+        };
+        if c == '-' && (slug.is_empty() || slug.ends_with('-')) {
+            continue;
+        }
+        slug.push(c);
+    }
+    let truncated: String = slug.chars().take(MAX_SLUG_LEN).collect();
+    truncated.trim_end_matches('-').to_string()
+}
+
+/// Validate a `title` parameter as a single safe directory name under the
+/// council root. Returns the slug unchanged when it already follows the rules.
+pub fn validate_slug(raw: &str) -> Result<&str, InvalidSlug> {
+    let suggestion = Some(normalize_slug(raw)).filter(|s| !s.is_empty());
+    let invalid = |reason: &str| InvalidSlug {
+        title: raw.to_string(),
+        reason: reason.to_string(),
+        suggestion: suggestion.clone(),
+    };
+
+    if raw.trim().is_empty() {
+        return Err(invalid("title must not be empty"));
+    }
+    if raw.starts_with('/') || raw.starts_with('\\') || raw.chars().nth(1) == Some(':') {
+        return Err(invalid("absolute paths are not allowed"));
+    }
+    if raw.contains("..") || raw.contains('/') || raw.contains('\\') {
+        return Err(invalid("path separators and traversal are not allowed"));
+    }
+    if raw.len() > MAX_SLUG_LEN {
+        return Err(invalid(&format!("title must be at most {} characters", MAX_SLUG_LEN)));
+    }
+    if suggestion.as_deref() != Some(raw) {
+        return Err(invalid(
+            "title must be lower-case and contain only [a-z0-9-], without leading, trailing or repeated '-'",
+        ));
+    }
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The rejection reason and suggested slug for `raw`.
+    fn rejected(raw: &str) -> (String, Option<String>) {
+        let e = validate_slug(raw).expect_err(raw);
+        assert_eq!(e.title, raw);
+        (e.reason, e.suggestion)
+    }
+
+    #[test]
+    fn traversal_is_rejected() {
+        let (reason, suggestion) = rejected("../x");
+        assert!(reason.contains("traversal"), "{}", reason);
+        assert_eq!(suggestion.as_deref(), Some("x"));
+
+        let (reason, suggestion) = rejected("..");
+        assert!(reason.contains("traversal"), "{}", reason);
+        assert_eq!(suggestion, None);
+    }
+
+    #[test]
+    fn absolute_paths_are_rejected() {
+        for (raw, suggestion) in [("/abs", "abs"), ("\\abs", "abs"), ("C:", "c"), ("C:\\x", "c-x")] {
+            let (reason, suggested) = rejected(raw);
+            assert!(reason.contains("absolute"), "{}: {}", raw, reason);
+            assert_eq!(suggested.as_deref(), Some(suggestion), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn separators_are_rejected() {
+        for raw in ["a/b", "a\\b"] {
+            let (reason, suggestion) = rejected(raw);
+            assert!(reason.contains("separators"), "{}: {}", raw, reason);
+            assert_eq!(suggestion.as_deref(), Some("a-b"));
+        }
+    }
+
+    #[test]
+    fn empty_titles_are_rejected() {
+        for raw in ["", "   "] {
+            let (reason, suggestion) = rejected(raw);
+            assert!(reason.contains("empty"), "{}", reason);
+            assert_eq!(suggestion, None);
+        }
+    }
+
+    #[test]
+    fn unnormalized_titles_get_a_suggestion() {
+        for (raw, suggestion) in [
+            ("My Title", "my-title"),
+            ("Rust_Error Handling!", "rust-error-handling"),
+            ("-edge--", "edge"),
+        ] {
+            let (reason, suggested) = rejected(raw);
+            assert!(reason.contains("lower-case"), "{}: {}", raw, reason);
+            assert_eq!(suggested.as_deref(), Some(suggestion), "{}", raw);
+        }
+
+        let e = validate_slug("Bad Title").unwrap_err();
+        assert_eq!(e.to_error_data()["suggestion"], "bad-title");
+        assert!(e.to_string().contains("did you mean \"bad-title\""), "{}", e);
+    }
+
+    #[test]
+    fn long_titles_are_rejected() {
+        let raw = "a".repeat(MAX_SLUG_LEN + 1);
+        let (reason, suggestion) = rejected(&raw);
+        assert!(reason.contains("at most"), "{}", reason);
+        assert_eq!(suggestion.map(|s| s.len()), Some(MAX_SLUG_LEN));
+    }
+
+    #[test]
+    fn valid_slugs_pass_unchanged() {
+        for raw in ["rust-error-handling", "q3-2025", "a"] {
+            assert_eq!(validate_slug(raw).unwrap(), raw);
+            assert_eq!(normalize_slug(raw), raw);
+        }
+    }
+}
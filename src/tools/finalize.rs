@@ -1,25 +1,120 @@
+use crate::aggregate;
+use crate::client::{ClientPeer, SAMPLING_MAX_TOKENS};
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::progress::Progress;
+use crate::ranking;
+use crate::slug::validate_slug;
+use crate::tools::save_final::handle_save_final;
+use crate::tools::{sanitize_model, CouncilTool, ToolContext};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde_json::{json, Value};
-use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-fn find_council_dir() -> Result<PathBuf> {
-    let home = env::var("HOME").context("HOME not set")?;
-    let council = PathBuf::from(home).join(".council");
-    if !council.exists() {
-        return Err(anyhow::anyhow!(
-            "Council directory not found: {}",
-            council.display()
-        ));
+pub struct FinalizeTool;
+
+#[async_trait]
+impl CouncilTool for FinalizeTool {
+    fn name(&self) -> &'static str {
+        "council.finalize"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stage3: Read Stage1 and Stage2 JSON files and generate final answer using local LLM CLI"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "LLM model name performing the synthesis (examples: claude, gemini, glm-4.6)",
+                    "default": "claude"
+                },
+                "engine": {
+                    "type": "string",
+                    "description": "LLM model/engine (for backward compatibility, use 'model' instead)",
+                    "default": "claude"
+                },
+                "use_sampling": {
+                    "type": "boolean",
+                    "description": "When the client supports sampling, have it write the final answer and save it in this call (default: true)",
+                    "default": true
+                }
+            },
+            "required": ["title"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "action": {
+                    "type": "string",
+                    "enum": ["synthesize_final_answer", "final_answer_saved"],
+                    "description": "final_answer_saved when the synthesis was sampled from the client and already saved"
+                },
+                "data": {
+                    "type": "object",
+                    "description": "Stage1 answers, Stage2 reviews, tally and the chairman prompt",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "user_query": { "type": "string" },
+                        "stage1_results": { "type": "array", "items": { "type": "object" } },
+                        "stage2_results": { "type": "array", "items": { "type": "object" } },
+                        "tally": { "type": "object" },
+                        "model": { "type": "string" },
+                        "engine": { "type": "string" },
+                        "chairman_prompt": { "type": "string" }
+                    }
+                },
+                "output_file": { "type": "string" },
+                "output_dir": { "type": "string" },
+                "tally": { "type": "object" },
+                "instruction": { "type": "string" },
+                "sampling_error": {
+                    "type": "string",
+                    "description": "Why sampling was attempted but the prompt is returned instead"
+                },
+                "sampled_model": { "type": ["string", "null"] },
+                "stop_reason": { "type": ["string", "null"] },
+                "saved": {
+                    "type": "object",
+                    "description": "The council.save_final result for the sampled answer"
+                }
+            },
+            "required": ["success", "action", "output_file", "output_dir"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Finalize failed"
+    }
+
+    async fn call(&self, arguments: Value, ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_finalize(arguments, ctx.sampler, ctx.progress).await
     }
-    Ok(council)
 }
 
-pub async fn handle_finalize(params: Value) -> Result<Value> {
-    let title = params["title"]
-        .as_str()
-        .context("Missing required parameter: title")?;
+pub async fn handle_finalize(
+    params: Value,
+    sampler: Option<&ClientPeer>,
+    progress: &Progress,
+) -> Result<Value> {
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
     // Try to get model from various sources in priority order:
     // 1. Explicit model parameter
     // 2. engine parameter (for backward compatibility)
@@ -31,24 +126,7 @@ pub async fn handle_finalize(params: Value) -> Result<Value> {
     let model_trimmed = model_raw.trim();
     let model = if model_trimmed.is_empty() { "claude" } else { model_trimmed };
     
-    let model_for_file: String = {
-        let sanitized: String = model
-            .chars()
-            .map(|c: char| {
-                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
-                    c
-                } else {
-                    '-'
-                }
-            })
-            .collect();
-        let cleaned = sanitized.trim_matches('-');
-        if cleaned.is_empty() {
-            "claude".to_string()
-        } else {
-            sanitized
-        }
-    };
+    let model_for_file = sanitize_model(model);
     
     // Keep engine for backward compatibility (use model if engine not explicitly set)
     let engine = params["engine"]
@@ -59,7 +137,7 @@ pub async fn handle_finalize(params: Value) -> Result<Value> {
     let base_dir = council_base.join(title);
     
     // Debug logging
-    eprintln!("DEBUG: finalize called with params: title={}, model={}, council_base={}, base_dir={}",
+    log_debug!("finalize called with params: title={}, model={}, council_base={}, base_dir={}",
         title, model, council_base.display(), base_dir.display());
     
     if !base_dir.exists() {
@@ -70,59 +148,31 @@ pub async fn handle_finalize(params: Value) -> Result<Value> {
         ));
     }
 
+    // Stage inputs come from the session manifest; answers and reviews saved
+    // directly by a client are adopted into it first
+    let lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+    manifest.adopt_untracked_answers(&base_dir)?;
+    manifest.adopt_untracked_reviews(&base_dir)?;
+
+    // Steps: answers, one per review, aggregation, then sampling and saving
+    let use_sampling = sampler.is_some() && params["use_sampling"].as_bool().unwrap_or(true);
+    let review_paths = manifest.review_paths(&base_dir);
+    progress.set_total(2 + review_paths.len() as u64 + if use_sampling { 2 } else { 0 });
+
     // Load Stage1 answers (markdown preferred, JSON for backward compatibility)
-    let answer_files: Vec<PathBuf> = fs::read_dir(&base_dir)
-        .context(format!("Failed to read directory: {}", base_dir.display()))?
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            let file_name = path.file_name()?.to_string_lossy();
-            
-            if file_name.contains("-answer.md") || file_name.ends_with("answer.md")
-                || file_name.contains("-answer.json") || file_name.ends_with("answer.json") {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect();
+    let stage1_results = load_stage1_answers(&manifest.answer_paths(&base_dir))?;
+    progress.step(format!("{} Stage1 answer(s) loaded", stage1_results.len()));
 
-    if answer_files.is_empty() {
+    if stage1_results.is_empty() {
         return Err(anyhow::anyhow!(
             "No Stage1 answer files found in {}",
             base_dir.display()
         ));
     }
 
-    let mut stage1_results = Vec::new();
-    for file_path in &answer_files {
-        let parsed = read_stage1_answer(file_path)
-            .context(format!("Failed to parse answer file: {}", file_path.display()))?;
-        stage1_results.push(parsed);
-    }
-
     // Load Stage2 reviews (markdown preferred, JSON for backward compatibility)
-    let review_files: Vec<PathBuf> = fs::read_dir(&base_dir)
-        .context(format!("Failed to read directory: {}", base_dir.display()))?
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            let file_name = path.file_name()?.to_string_lossy();
-            
-            if file_name.contains("peer-review") {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let mut stage2_results = Vec::new();
-    for file_path in &review_files {
-        let parsed = read_stage2_review(file_path)
-            .context(format!("Failed to parse review file: {}", file_path.display()))?;
-        stage2_results.push(parsed);
-    }
+    let stage2_results = load_stage2_reviews(&review_paths, title, progress)?;
 
     if stage2_results.is_empty() {
         return Err(anyhow::anyhow!(
@@ -130,8 +180,7 @@ pub async fn handle_finalize(params: Value) -> Result<Value> {
         ));
     }
 
-    // Extract user query
-    let user_query = extract_user_query(&base_dir)?;
+    let user_query = manifest.query().to_string();
 
     // Build Stage1 text
     let stage1_text = stage1_results
@@ -166,11 +215,52 @@ pub async fn handle_finalize(params: Value) -> Result<Value> {
                 .or_else(|| result.get("content"))
                 .and_then(|v| v.as_str())
                 .unwrap_or("No review content");
-            format!("Model: {}\nRanking: {}", model, review)
+            let parsed_ranking = result
+                .get("ranking")
+                .and_then(|r| r.get("ranking"))
+                .and_then(|r| r.as_array())
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .map(|e| {
+                            format!(
+                                "{}. {}",
+                                e["rank"].as_u64().unwrap_or(0),
+                                e["model"].as_str().or(e["label"].as_str()).unwrap_or("unknown")
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                });
+            match parsed_ranking {
+                Some(parsed) => format!(
+                    "Model: {}\nParsed Ranking (best to worst):\n{}\nReview: {}",
+                    model, parsed, review
+                ),
+                None => format!("Model: {}\nRanking: {}", model, review),
+            }
         })
         .collect::<Vec<_>>()
         .join("\n\n");
 
+    // Aggregate the parsed rankings across reviewers
+    let candidates: Vec<String> = stage1_results
+        .iter()
+        .filter_map(|r| r.get("model").and_then(|v| v.as_str()).map(|m| m.to_string()))
+        .collect();
+    let ballots = aggregate::ballots_from_reviews(&stage2_results);
+    let tally = aggregate::tally(&candidates, &ballots);
+    progress.step(format!("Aggregated {} ballot(s)", ballots.len()));
+    let tally_text = if ballots.is_empty() {
+        "No parseable FINAL RANKING sections; rely on the reviews above.".to_string()
+    } else {
+        format!(
+            "Combined from {} ballot(s), ordered by Schulze with Borda and mean rank as tie-breakers:\n{}",
+            ballots.len(),
+            tally.summary()
+        )
+    };
+
     // Build chairman prompt
     let chairman_prompt = format!(
         r#"You are the Chairman of an LLM Council. Multiple AI models have provided responses to a user's question, and then ranked each other's responses.
@@ -183,17 +273,66 @@ STAGE 1 - Individual Responses:
 STAGE 2 - Peer Rankings:
 {}
 
+AGGREGATE RANKING:
+{}
+
 Your task as Chairman is to synthesize all of this information into a single, comprehensive, accurate answer to the user's original question. Consider:
 - The individual responses and their insights
 - The peer rankings and what they reveal about response quality
 - Any patterns of agreement or disagreement
 
 Provide a clear, well-reasoned final answer that represents the council's collective wisdom:"#,
-        user_query, stage1_text, stage2_text
+        user_query, stage1_text, stage2_text, tally_text
     );
 
+    // save_final reports these inputs even if more arrive before it runs
+    let output_file = format!("final-answer-by-{}.md", model_for_file);
+    manifest.record_finalize_request(
+        model,
+        &output_file,
+        &manifest.answer_paths(&base_dir),
+        &review_paths,
+    )?;
+    manifest.save(&base_dir)?;
+    // Sampling below may take minutes and saves through save_review /
+    // save_final, which take the lock themselves
+    drop(lock);
+
+    // With sampling the client's model acts as chairman and the final answer
+    // is saved here instead of by a separate save_final call
+    let mut sampling_error = None;
+    if let Some(client) = sampler.filter(|_| use_sampling) {
+        log_debug!("finalize - requesting synthesis from client via sampling");
+        progress.step("Synthesizing final answer");
+        match client.create_message(&chairman_prompt, model, SAMPLING_MAX_TOKENS).await {
+            Ok(sampled) => {
+                progress.step("Final answer received, saving");
+                let saved = handle_save_final(json!({
+                    "title": title,
+                    "model": model,
+                    "content": sampled.text
+                }))
+                .await?;
+                return Ok(json!({
+                    "success": true,
+                    "action": "final_answer_saved",
+                    "output_file": output_file,
+                    "output_dir": base_dir.display().to_string(),
+                    "tally": tally,
+                    "sampled_model": sampled.model,
+                    "stop_reason": sampled.stop_reason,
+                    "saved": saved
+                }));
+            }
+            Err(e) => {
+                log_warn!("finalize - sampling failed, returning prompt instead: {}", e);
+                sampling_error = Some(e.to_string());
+            }
+        }
+    }
+
     // Return the data and prompt for the current model to process directly
-    Ok(json!({
+    let mut result = json!({
         "success": true,
         "action": "synthesize_final_answer",
         "data": {
@@ -201,79 +340,83 @@ Provide a clear, well-reasoned final answer that represents the council's collec
             "user_query": user_query,
             "stage1_results": stage1_results,
             "stage2_results": stage2_results,
+            "tally": tally,
             "model": model,
             "engine": engine,
             "chairman_prompt": chairman_prompt
         },
-        "output_file": format!("final-answer-by-{}.md", model_for_file),
+        "output_file": output_file,
         "output_dir": base_dir.display().to_string(),
-        "instruction": "As Chairman of the LLM Council, please synthesize all provided information into a comprehensive final answer to the user's question. When you're done, I'll save it to the specified file."
-    }))
+        "instruction": "As Chairman of the LLM Council, please synthesize all provided information into a comprehensive final answer to the user's question. When you're done, save it with council.save_final using the same title and model."
+    });
+    if let Some(error) = sampling_error {
+        result["sampling_error"] = json!(error);
+    }
+    Ok(result)
 }
 
-
-fn extract_user_query(base_dir: &Path) -> Result<String> {
-    // Try to find the original query in various possible locations
-    let possible_files = [
-        "query.txt",
-        "user_query.txt",
-        "question.txt",
-        "input.txt",
-    ];
-
-    for file_name in &possible_files {
-        let file_path = base_dir.join(file_name);
-        if file_path.exists() {
-            return Ok(fs::read_to_string(&file_path)?
-                .trim()
-                .to_string());
-        }
+pub(crate) fn load_stage1_answers(answer_files: &[PathBuf]) -> Result<Vec<Value>> {
+    let mut stage1_results = Vec::new();
+    for file_path in answer_files {
+        let parsed = read_stage1_answer(file_path)
+            .context(format!("Failed to parse answer file: {}", file_path.display()))?;
+        stage1_results.push(parsed);
     }
+    Ok(stage1_results)
+}
 
-    // Try to extract from answer files (both JSON and Markdown)
-    let answer_files: Vec<PathBuf> = fs::read_dir(base_dir)
-        .context("Failed to read directory")?
+/// Peer review files in a council directory, excluding ranking/label sidecars.
+pub(crate) fn list_review_files(base_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut review_files: Vec<PathBuf> = fs::read_dir(base_dir)
+        .context(format!("Failed to read directory: {}", base_dir.display()))?
         .filter_map(|entry| {
             let entry = entry.ok()?;
             let path = entry.path();
             let file_name = path.file_name()?.to_string_lossy();
-            if file_name.contains("-answer.md") || file_name.ends_with("answer.md")
-                || file_name.contains("-answer.json") || file_name.ends_with("answer.json") {
+
+            if file_name.contains("peer-review") && !ranking::is_sidecar(&file_name) {
                 Some(path)
             } else {
                 None
             }
         })
         .collect();
+    review_files.sort();
+    Ok(review_files)
+}
 
-    if let Some(first_file) = answer_files.first() {
-        let content = fs::read_to_string(first_file)?;
-        
-        // Try JSON format first
-        if let Ok(json_data) = serde_json::from_str::<Value>(&content) {
-            if let Some(query) = json_data.get("query").or(json_data.get("user_query")) {
-                if let Some(query_str) = query.as_str() {
-                    return Ok(query_str.to_string());
-                }
-            }
-        }
-        
-        // Try Markdown format: look for "- prompt: {prompt}" pattern
-        for line in content.lines() {
-            let line = line.trim();
-            if line.starts_with("- prompt:") || line.starts_with("-prompt:") {
-                let prompt = line
-                    .trim_start_matches("- prompt:")
-                    .trim_start_matches("-prompt:")
-                    .trim();
-                if !prompt.is_empty() {
-                    return Ok(prompt.to_string());
-                }
-            }
+/// Load every Stage2 review along with its structured ranking, if any.
+pub(crate) fn load_stage2_reviews(
+    review_files: &[PathBuf],
+    title: &str,
+    progress: &Progress,
+) -> Result<Vec<Value>> {
+    let mut stage2_results = Vec::new();
+    for (idx, file_path) in review_files.iter().enumerate() {
+        let mut parsed = read_stage2_review(file_path)
+            .context(format!("Failed to parse review file: {}", file_path.display()))?;
+        // Prefer the ranking saved by save_review; otherwise resolve the
+        // review's labels through the mapping peer_review recorded
+        let review_ranking = ranking::load_ranking(file_path).or_else(|| {
+            ranking::build_review_ranking(
+                title,
+                parsed["model"].as_str().unwrap_or("unknown-model"),
+                file_path,
+                parsed["review"].as_str().unwrap_or_default(),
+            )
+        });
+        if let Some(review_ranking) = review_ranking {
+            parsed["ranking"] = serde_json::to_value(&review_ranking)?;
         }
+        progress.step(format!(
+            "Review {}/{} loaded ({})",
+            idx + 1,
+            review_files.len(),
+            parsed["model"].as_str().unwrap_or("unknown-model")
+        ));
+        stage2_results.push(parsed);
     }
-
-    Ok("Unknown query".to_string())
+    Ok(stage2_results)
 }
 
 fn read_stage1_answer(path: &Path) -> Result<Value> {
@@ -305,7 +448,7 @@ fn read_stage1_answer(path: &Path) -> Result<Value> {
     }))
 }
 
-fn read_stage2_review(path: &Path) -> Result<Value> {
+pub(crate) fn read_stage2_review(path: &Path) -> Result<Value> {
     let content = fs::read_to_string(path)
         .context(format!("Failed to read file: {}", path.display()))?;
 
@@ -1,44 +1,83 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::slug::validate_slug;
+use crate::tools::{sanitize_model, CouncilTool, ToolContext};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use chrono::Utc;
 use serde_json::{json, Value};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
-fn find_council_dir() -> Result<PathBuf> {
-    let home = env::var("HOME").context("HOME not set")?;
-    let council = PathBuf::from(home).join(".council");
-    if !council.exists() {
-        fs::create_dir_all(&council)
-            .context(format!("Failed to create council directory: {}", council.display()))?;
+pub struct FirstAnswerTool;
+
+#[async_trait]
+impl CouncilTool for FirstAnswerTool {
+    fn name(&self) -> &'static str {
+        "council.first_answer"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stage1: Save current model answer into .council/{slug}/{model}-answer.md"
     }
-    Ok(council)
-}
 
-fn sanitize_model(model: &str) -> String {
-    let lowered = model.to_lowercase();
-    let sanitized: String = lowered
-        .chars()
-        .map(|c: char| {
-            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
-                c
-            } else {
-                '-'
-            }
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name (slug)"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "Model name (e.g., sonnet, gemini, gpt-5.1)",
+                    "default": "unknown-model"
+                },
+                "prompt": {
+                    "type": "string",
+                    "description": "User question or prompt text"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Full model answer content to save"
+                }
+            },
+            "required": ["title", "prompt", "content"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "file_saved": {
+                    "type": "string",
+                    "description": "Path of the saved Stage1 answer"
+                },
+                "summary": { "type": "string" }
+            },
+            "required": ["success", "file_saved", "summary"]
         })
-        .collect();
-    let cleaned = sanitized.trim_matches('-');
-    if cleaned.is_empty() {
-        "unknown-model".to_string()
-    } else {
-        sanitized
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Stage1 save failed"
+    }
+
+    async fn call(&self, arguments: Value, _ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_first_answer(arguments).await
     }
 }
 
 pub async fn handle_first_answer(params: Value) -> Result<Value> {
-    let title = params["title"]
-        .as_str()
-        .context("Missing required parameter: title")?;
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
     let model_raw = params["model"]
         .as_str()
         .unwrap_or("unknown-model")
@@ -56,8 +95,8 @@ pub async fn handle_first_answer(params: Value) -> Result<Value> {
         .context("Missing required parameter: content")?;
 
     // Debug logging
-    eprintln!(
-        "DEBUG: first_answer called with params: title={}, model={}, prompt_len={}, content_len={}",
+    log_debug!(
+        "first_answer called with params: title={}, model={}, prompt_len={}, content_len={}",
         title,
         model,
         prompt.len(),
@@ -73,6 +112,9 @@ pub async fn handle_first_answer(params: Value) -> Result<Value> {
         base_dir.display()
     ))?;
 
+    let _lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+
     let model_for_file = sanitize_model(model);
     let mut file_name = format!("{}-answer.md", model_for_file);
     let mut file_path = base_dir.join(&file_name);
@@ -99,6 +141,13 @@ pub async fn handle_first_answer(params: Value) -> Result<Value> {
             .display()
     ))?;
 
+    // The first answer's prompt becomes the session's canonical query
+    manifest.record_answer(model, &file_path)?;
+    if manifest.query.is_none() {
+        manifest.query = Some(prompt.trim().to_string());
+    }
+    manifest.save(&base_dir)?;
+
     Ok(json!({
         "success": true,
         "file_saved": file_path.to_string_lossy(),
@@ -4,4 +4,114 @@ pub mod save_review;
 pub mod first_answer;
 pub mod summarize;
 pub mod save_summary;
+pub mod tally;
+pub mod save_final;
+pub mod status;
 
+use crate::client::ClientPeer;
+use crate::progress::Progress;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+/// The model name as it appears in file names (`{model}-answer.md`,
+/// `peer-review-by-{model}.md`, `final-answer-by-{model}.md`). Every stage
+/// uses this one function so the names cannot drift apart.
+pub(crate) fn sanitize_model(model: &str) -> String {
+    let lowered = model.to_lowercase();
+    let sanitized: String = lowered
+        .chars()
+        .map(|c: char| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let cleaned = sanitized.trim_matches('-');
+    if cleaned.is_empty() {
+        "unknown-model".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// What a tool call gets from the connection besides its arguments.
+pub struct ToolContext<'a> {
+    /// The client, when it supports `sampling/createMessage`
+    pub sampler: Option<&'a ClientPeer>,
+    /// Progress reporting for the call (a no-op without a progress token)
+    pub progress: &'a Progress,
+}
+
+/// A `council.*` tool: what `tools/list` advertises and what `tools/call`
+/// runs, defined next to the handler so the two cannot drift apart.
+#[async_trait]
+pub trait CouncilTool: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    fn input_schema(&self) -> Value;
+
+    /// Shape of the JSON object the handler returns, sent to the client as
+    /// `structuredContent`.
+    fn output_schema(&self) -> Value;
+
+    /// Prefix for the error message when the handler fails.
+    fn failure_message(&self) -> &'static str;
+
+    async fn call(&self, arguments: Value, ctx: &ToolContext<'_>) -> Result<Value>;
+}
+
+/// Every tool the server exposes, in `tools/list` order.
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn CouncilTool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self {
+            tools: vec![
+                Box::new(first_answer::FirstAnswerTool),
+                Box::new(peer_review::PeerReviewTool),
+                Box::new(finalize::FinalizeTool),
+                Box::new(tally::TallyTool),
+                Box::new(status::StatusTool),
+                Box::new(save_review::SaveReviewTool),
+                Box::new(save_final::SaveFinalTool),
+                Box::new(summarize::SummarizeTool),
+                Box::new(save_summary::SaveSummaryTool),
+            ],
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn CouncilTool> {
+        self.tools
+            .iter()
+            .find(|tool| tool.name() == name)
+            .map(|tool| tool.as_ref())
+    }
+
+    /// The `tools/list` result. Output schemas are only advertised to
+    /// clients whose protocol revision has structured output.
+    pub fn list(&self, structured_output: bool) -> Value {
+        let tools: Vec<Value> = self
+            .tools
+            .iter()
+            .map(|tool| {
+                let mut entry = json!({
+                    "name": tool.name(),
+                    "description": tool.description(),
+                    "inputSchema": tool.input_schema()
+                });
+                if structured_output {
+                    entry["outputSchema"] = tool.output_schema();
+                }
+                entry
+            })
+            .collect();
+        json!({ "tools": tools })
+    }
+}
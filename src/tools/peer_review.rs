@@ -1,25 +1,112 @@
+use crate::client::{ClientPeer, SAMPLING_MAX_TOKENS};
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::progress::Progress;
+use crate::ranking::{self, LabelAssignment, LabelMapping};
+use crate::slug::validate_slug;
+use crate::tools::save_review::handle_save_review;
+use crate::tools::{sanitize_model, CouncilTool, ToolContext};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
 use serde_json::{json, Value};
-use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-fn find_council_dir() -> Result<PathBuf> {
-    let home = env::var("HOME").context("HOME not set")?;
-    let council = PathBuf::from(home).join(".council");
-    if !council.exists() {
-        return Err(anyhow::anyhow!(
-            "Council directory not found: {}",
-            council.display()
-        ));
+pub struct PeerReviewTool;
+
+#[async_trait]
+impl CouncilTool for PeerReviewTool {
+    fn name(&self) -> &'static str {
+        "council.peer_review"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stage2: Read Stage1 JSON files and generate peer review using local LLM CLI"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "LLM model name performing the review (examples: claude, gemini, glm-4.6)",
+                    "default": "claude"
+                },
+                "self_model": {
+                    "type": "string",
+                    "description": "Model name to exclude from peer review (its own response)"
+                },
+                "use_sampling": {
+                    "type": "boolean",
+                    "description": "When the client supports sampling, have it write the review and save it in this call (default: true)",
+                    "default": true
+                }
+            },
+            "required": ["title"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "action": {
+                    "type": "string",
+                    "enum": ["perform_peer_review_and_save", "peer_review_saved"],
+                    "description": "peer_review_saved when the review was sampled from the client and already saved"
+                },
+                "review_request": {
+                    "type": "string",
+                    "description": "Prompt with the anonymized answers to review"
+                },
+                "output_file": { "type": "string" },
+                "output_dir": { "type": "string" },
+                "labels_file": {
+                    "type": "string",
+                    "description": "Mapping of anonymized labels to models for this reviewer"
+                },
+                "instruction": { "type": "string" },
+                "sampling_error": {
+                    "type": "string",
+                    "description": "Why sampling was attempted but the prompt is returned instead"
+                },
+                "sampled_model": { "type": ["string", "null"] },
+                "stop_reason": { "type": ["string", "null"] },
+                "saved": {
+                    "type": "object",
+                    "description": "The council.save_review result for the sampled review"
+                }
+            },
+            "required": ["success", "action", "output_file", "output_dir", "labels_file"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Peer review failed"
+    }
+
+    async fn call(&self, arguments: Value, ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_peer_review(arguments, ctx.sampler, ctx.progress).await
     }
-    Ok(council)
 }
 
-pub async fn handle_peer_review(params: Value) -> Result<Value> {
-    let title = params["title"]
-        .as_str()
-        .context("Missing required parameter: title")?;
+pub async fn handle_peer_review(
+    params: Value,
+    sampler: Option<&ClientPeer>,
+    progress: &Progress,
+) -> Result<Value> {
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
     // Try to get model from various sources in priority order:
     // 1. Explicit model parameter
     // 2. self_model (when model is not explicitly set but self_model is)
@@ -33,35 +120,18 @@ pub async fn handle_peer_review(params: Value) -> Result<Value> {
     let model_trimmed = model_raw.trim();
     let model = if model_trimmed.is_empty() { "claude" } else { model_trimmed };
 
-    let model_for_file: String = {
-        let sanitized: String = model
-            .chars()
-            .map(|c: char| {
-                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
-                    c
-                } else {
-                    '-'
-                }
-            })
-            .collect();
-        let cleaned = sanitized.trim_matches('-');
-        if cleaned.is_empty() {
-            "claude".to_string()
-        } else {
-            sanitized
-        }
-    };
+    let model_for_file = sanitize_model(model);
     let self_model = params.get("self_model").and_then(|v| v.as_str());
 
     // Debug logging
-    eprintln!("DEBUG: peer_review called with params: title={}, model={}, self_model={}",
+    log_debug!("peer_review called with params: title={}, model={}, self_model={}",
         title, model, self_model.unwrap_or("None"));
 
     let council_base = find_council_dir()?;
     let base_dir = council_base.join(title);
     
     // Debug logging
-    eprintln!("DEBUG: peer_review - council_base={}, base_dir={}",
+    log_debug!("peer_review - council_base={}, base_dir={}",
         council_base.display(), base_dir.display());
     
     if !base_dir.exists() {
@@ -72,57 +142,15 @@ pub async fn handle_peer_review(params: Value) -> Result<Value> {
         ));
     }
 
-    // Find all Stage1 answer files (markdown preferred, JSON for backward compatibility)
-    let answer_files: Vec<PathBuf> = fs::read_dir(&base_dir)
-        .context(format!("Failed to read directory: {}", base_dir.display()))?
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            let file_name = path.file_name()?.to_string_lossy();
-            
-            if file_name.contains("-answer.md") || file_name.ends_with("answer.md")
-                || file_name.contains("-answer.json") || file_name.ends_with("answer.json") {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    if answer_files.is_empty() {
-        return Err(anyhow::anyhow!(
-            "No Stage1 answer files found in {}",
-            base_dir.display()
-        ));
-    }
-
-    // Load and parse all answer files, optionally excluding self_model
-    let mut answers = Vec::new();
-    let mut labels = Vec::new();
-    for file_path in answer_files.iter() {
-        let content_value = read_stage1_answer(file_path)
-            .context(format!("Failed to parse answer file: {}", file_path.display()))?;
-
-        let model_name = content_value
-            .get("model")
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown-model");
-
-        if let Some(self_model_name) = self_model {
-            if model_name.eq_ignore_ascii_case(self_model_name) {
-                eprintln!(
-                    "INFO: Skipping self_model '{}' from peer review",
-                    self_model_name
-                );
-                continue;
-            }
-        }
+    // Steps: answers labeled, then sampling and saving
+    let use_sampling = sampler.is_some() && params["use_sampling"].as_bool().unwrap_or(true);
+    progress.set_total(if use_sampling { 3 } else { 1 });
 
-        answers.push(json!({
-            "file": file_path.file_name().unwrap().to_string_lossy(),
-            "content": content_value
-        }));
-    }
+    let lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+    manifest.adopt_untracked_answers(&base_dir)?;
+    let answers = label_answers(&base_dir, &manifest.answer_paths(&base_dir), self_model)?;
+    progress.step(format!("{} answer(s) labeled for review", answers.len()));
 
     if answers.is_empty() {
         return Err(anyhow::anyhow!(
@@ -130,29 +158,41 @@ pub async fn handle_peer_review(params: Value) -> Result<Value> {
         ));
     }
 
-    // Re-label responses after exclusion to keep labels consecutive
-    for (idx, answer) in answers.iter_mut().enumerate() {
-        let label = format!("Response {}", char::from(b'A' + idx as u8));
-        labels.push(label.clone());
-        answer["label"] = json!(label);
-    }
+    // Persist which answer each label points at so the saved review can be
+    // traced back to real models
+    let review_file = format!("peer-review-by-{}.md", model_for_file);
+    let mapping = LabelMapping {
+        title: title.to_string(),
+        reviewer: model.to_string(),
+        self_model: self_model.map(|s| s.to_string()),
+        created_at: Utc::now().to_rfc3339(),
+        labels: answers
+            .iter()
+            .map(|a| LabelAssignment {
+                label: a.label.clone(),
+                model: a.model.clone(),
+                file: a.file.clone(),
+                sha256: a.sha256.clone(),
+            })
+            .collect(),
+    };
+    let labels_path = ranking::save_label_mapping(&base_dir.join(&review_file), &mapping)?;
+    manifest.record_review_request(model, &review_file, &labels_path);
+    manifest.save(&base_dir)?;
+    // Sampling below may take minutes and saves through save_review /
+    // save_final, which take the lock themselves
+    drop(lock);
 
     // Build review prompt
-    let user_query = extract_user_query(&base_dir)?;
+    let user_query = manifest.query().to_string();
     
     let responses_text = answers
         .iter()
-        .map(|a| {
-            format!(
-                "{}:\n{}",
-                a["label"].as_str().unwrap(),
-                format_response_content(&a["content"])
-            )
-        })
+        .map(|a| format!("{}:\n{}", a.label, format_response_content(&a.content)))
         .collect::<Vec<_>>()
         .join("\n\n");
 
-    let _ranking_prompt = format!(
+    let ranking_prompt = format!(
         r#"You are evaluating different responses to the following question:
 
 Question: {}
@@ -207,17 +247,149 @@ After you complete your review, the system will save it as: peer-review-by-{}.md
         user_query, responses_text, model
     );
 
-    Ok(json!({
+    // With sampling the client's model writes the review and we save it here,
+    // so the caller does not need a second save_review call
+    let mut sampling_error = None;
+    if let Some(client) = sampler.filter(|_| use_sampling) {
+        log_debug!("peer_review - requesting review from client via sampling");
+        progress.step(format!("Requesting review from {}", model));
+        match client.create_message(&ranking_prompt, model, SAMPLING_MAX_TOKENS).await {
+            Ok(sampled) => {
+                progress.step("Review received, saving");
+                let saved = handle_save_review(json!({
+                    "title": title,
+                    "model": model,
+                    "content": sampled.text
+                }))
+                .await?;
+                return Ok(json!({
+                    "success": true,
+                    "action": "peer_review_saved",
+                    "output_file": review_file,
+                    "output_dir": base_dir.display().to_string(),
+                    "labels_file": labels_path.to_string_lossy(),
+                    "sampled_model": sampled.model,
+                    "stop_reason": sampled.stop_reason,
+                    "saved": saved
+                }));
+            }
+            Err(e) => {
+                log_warn!("peer_review - sampling failed, returning prompt instead: {}", e);
+                sampling_error = Some(e.to_string());
+            }
+        }
+    }
+
+    let mut result = json!({
         "success": true,
         "action": "perform_peer_review_and_save",
         "review_request": review_request_prompt,
-        "output_file": format!("peer-review-by-{}.md", model_for_file),
+        "output_file": review_file,
         "output_dir": base_dir.display().to_string(),
+        "labels_file": labels_path.to_string_lossy(),
         "instruction": "Please provide your peer review evaluation. When you're done, I'll save it to the specified file."
-    }))
+    });
+    if let Some(error) = sampling_error {
+        result["sampling_error"] = json!(error);
+    }
+    Ok(result)
+}
+
+/// A Stage1 answer together with the anonymized label it is shown under.
+pub(crate) struct LabeledAnswer {
+    pub label: String,
+    pub model: String,
+    pub file: String,
+    pub sha256: String,
+    pub content: Value,
+}
+
+/// Stage1 answer files in a council directory, sorted by file name so that
+/// label assignment is stable across calls.
+pub(crate) fn list_answer_files(base_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut answer_files: Vec<PathBuf> = fs::read_dir(base_dir)
+        .context(format!("Failed to read directory: {}", base_dir.display()))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            let file_name = path.file_name()?.to_string_lossy();
+
+            if file_name.contains("-answer.md") || file_name.ends_with("answer.md")
+                || file_name.contains("-answer.json") || file_name.ends_with("answer.json") {
+                Some(path)
+            } else {
+                None
+            }
+        })
+        .collect();
+    answer_files.sort();
+    Ok(answer_files)
 }
 
-fn extract_user_query(base_dir: &Path) -> Result<String> {
+/// Load the given Stage1 answers, drop the one written by `self_model`, and assign
+/// consecutive "Response A/B/C" labels to the rest.
+pub(crate) fn label_answers(
+    base_dir: &Path,
+    answer_files: &[PathBuf],
+    self_model: Option<&str>,
+) -> Result<Vec<LabeledAnswer>> {
+    if answer_files.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No Stage1 answer files found in {}",
+            base_dir.display()
+        ));
+    }
+
+    let mut answers = Vec::new();
+    for file_path in answer_files.iter() {
+        let content_value = read_stage1_answer(file_path)
+            .context(format!("Failed to parse answer file: {}", file_path.display()))?;
+        let sha256 = ranking::sha256_hex(&fs::read(file_path)?);
+
+        let model_name = content_value
+            .get("model")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown-model")
+            .to_string();
+
+        if let Some(self_model_name) = self_model {
+            if same_model(&model_name, self_model_name) {
+                log_info!(
+                    "Skipping self_model '{}' from peer review",
+                    self_model_name
+                );
+                continue;
+            }
+        }
+
+        // Labels are consecutive after exclusion
+        let label = format!("Response {}", char::from(b'A' + answers.len() as u8));
+        answers.push(LabeledAnswer {
+            label,
+            model: model_name,
+            file: file_path.file_name().unwrap().to_string_lossy().to_string(),
+            sha256,
+            content: content_value,
+        });
+    }
+
+    Ok(answers)
+}
+
+/// Compare model names the way they appear in file names ("glm-4.6" and
+/// "glm-4-6" are the same model).
+pub(crate) fn same_model(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' })
+            .collect()
+    };
+    normalize(a) == normalize(b)
+}
+
+pub(crate) fn extract_user_query(base_dir: &Path) -> Result<String> {
     // Try to find the original query in various possible locations
     let possible_files = [
         "query.txt",
@@ -236,20 +408,7 @@ fn extract_user_query(base_dir: &Path) -> Result<String> {
     }
 
     // Try to extract from answer files (both JSON and Markdown)
-    let answer_files: Vec<PathBuf> = fs::read_dir(base_dir)
-        .context("Failed to read directory")?
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            let file_name = path.file_name()?.to_string_lossy();
-            if file_name.contains("-answer.md") || file_name.ends_with("answer.md")
-                || file_name.contains("-answer.json") || file_name.ends_with("answer.json") {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect();
+    let answer_files = list_answer_files(base_dir)?;
 
     if let Some(first_file) = answer_files.first() {
         let content = fs::read_to_string(first_file)?;
@@ -281,7 +440,7 @@ fn extract_user_query(base_dir: &Path) -> Result<String> {
     Ok("Unknown query".to_string())
 }
 
-fn read_stage1_answer(path: &Path) -> Result<Value> {
+pub(crate) fn read_stage1_answer(path: &Path) -> Result<Value> {
     let content = fs::read_to_string(path)
         .context(format!("Failed to read file: {}", path.display()))?;
 
@@ -0,0 +1,177 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::{describe_inputs, InputRecord, Manifest};
+use crate::slug::validate_slug;
+use crate::tools::{sanitize_model, CouncilTool, ToolContext};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub struct SaveFinalTool;
+
+#[async_trait]
+impl CouncilTool for SaveFinalTool {
+    fn name(&self) -> &'static str {
+        "council.save_final"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stage3: Save the chairman's final answer to final-answer-by-{model}.md, recording the Stage1 answers and Stage2 reviews it was based on"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "LLM model name that synthesized the final answer (examples: claude, gemini, glm-4.6)"
+                },
+                "engine": {
+                    "type": "string",
+                    "description": "LLM model/engine name (for backward compatibility, use 'model' instead)",
+                    "default": "claude"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Final answer content to save"
+                }
+            },
+            "required": ["title", "content"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "file_saved": {
+                    "type": "string",
+                    "description": "Path of the saved final answer"
+                },
+                "based_on": {
+                    "type": "object",
+                    "description": "File name and SHA-256 of each Stage1 answer and Stage2 review in the chairman prompt built by finalize",
+                    "properties": {
+                        "stage1_answers": { "type": "array", "items": { "type": "object" } },
+                        "stage2_reviews": { "type": "array", "items": { "type": "object" } }
+                    }
+                },
+                "summary": { "type": "string" }
+            },
+            "required": ["success", "file_saved", "summary"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Save final failed"
+    }
+
+    async fn call(&self, arguments: Value, _ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_save_final(arguments).await
+    }
+}
+
+fn markdown_list(inputs: &[InputRecord]) -> String {
+    if inputs.is_empty() {
+        return "  - (none)".to_string();
+    }
+    inputs
+        .iter()
+        .map(|i| format!("  - {} (sha256: {})", i.file, i.sha256))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub async fn handle_save_final(params: Value) -> Result<Value> {
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
+    // Try to get model from various sources in priority order:
+    // 1. Explicit model parameter
+    // 2. engine parameter (for backward compatibility)
+    // 3. Default to "claude"
+    let model_raw = params["model"]
+        .as_str()
+        .or_else(|| params["engine"].as_str())
+        .unwrap_or("claude");
+    let model_trimmed = model_raw.trim();
+    let model = if model_trimmed.is_empty() { "claude" } else { model_trimmed };
+
+    let model_for_file = sanitize_model(model);
+
+    let final_content = params["content"]
+        .as_str()
+        .context("Missing required parameter: content")?;
+
+    // Debug logging
+    log_debug!("save_final called with params: title={}, model={}, content_len={}",
+        title, model, final_content.len());
+
+    let council_base = find_council_dir()?;
+    let base_dir = council_base.join(title);
+
+    if !base_dir.exists() {
+        return Err(anyhow::anyhow!(
+            "Council directory not found: {}",
+            base_dir.display()
+        ));
+    }
+
+    let final_file = format!("final-answer-by-{}.md", model_for_file);
+
+    // Record the Stage1 answers and Stage2 reviews that finalize put in the
+    // chairman prompt; without a finalize request, those the session holds now
+    let _lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+    manifest.adopt_untracked_answers(&base_dir)?;
+    manifest.adopt_untracked_reviews(&base_dir)?;
+    let (stage1_inputs, stage2_inputs) = match manifest.finalize_request(&final_file) {
+        Some(request) => (request.stage1_answers.clone(), request.stage2_reviews.clone()),
+        None => (
+            describe_inputs(&manifest.answer_paths(&base_dir))?,
+            describe_inputs(&manifest.review_paths(&base_dir))?,
+        ),
+    };
+
+    // Build markdown content
+    let markdown = format!(
+        "# Final Answer\n- title: {}\n- model: {}\n- created_at: {}\n- stage1_answers:\n{}\n- stage2_reviews:\n{}\n\n## Final Answer Content\n\n{}",
+        title,
+        model,
+        Utc::now().to_rfc3339(),
+        markdown_list(&stage1_inputs),
+        markdown_list(&stage2_inputs),
+        final_content
+    );
+
+    // Save markdown file
+    let final_md_path = base_dir.join(&final_file);
+    fs::write(&final_md_path, &markdown)
+        .context(format!("Failed to write final answer file: {} (searched from: {})",
+            final_md_path.display(),
+            env::current_dir().unwrap_or_else(|_| PathBuf::from(".")).display()))?;
+
+    manifest.record_final(model, &final_md_path)?;
+    manifest.save(&base_dir)?;
+
+    Ok(json!({
+        "success": true,
+        "file_saved": final_md_path.to_string_lossy(),
+        "based_on": {
+            "stage1_answers": stage1_inputs,
+            "stage2_reviews": stage2_inputs
+        },
+        "summary": format!("Final answer saved to {}", final_md_path.display())
+    }))
+}
@@ -1,23 +1,95 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::ranking;
+use crate::slug::validate_slug;
+use crate::tools::{sanitize_model, CouncilTool, ToolContext};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
-fn find_council_dir() -> Result<PathBuf> {
-    let home = env::var("HOME").context("HOME not set")?;
-    let council = PathBuf::from(home).join(".council");
-    if !council.exists() {
-        fs::create_dir_all(&council)
-            .context(format!("Failed to create council directory: {}", council.display()))?;
+pub struct SaveReviewTool;
+
+#[async_trait]
+impl CouncilTool for SaveReviewTool {
+    fn name(&self) -> &'static str {
+        "council.save_review"
+    }
+
+    fn description(&self) -> &'static str {
+        "Save peer review content to markdown file"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "LLM model name (examples: claude, gemini, glm-4.6, gpt-4)"
+                },
+                "engine": {
+                    "type": "string",
+                    "description": "LLM model/engine name (for backward compatibility, use 'model' instead)",
+                    "default": "claude"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Peer review content to save"
+                }
+            },
+            "required": ["title", "content"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "file_saved": {
+                    "type": "string",
+                    "description": "Path of the saved peer review"
+                },
+                "ranking_file": {
+                    "type": "string",
+                    "description": "Path of the structured ranking parsed from the FINAL RANKING section"
+                },
+                "ranking": {
+                    "type": "object",
+                    "description": "The parsed ranking, with labels mapped back to model names"
+                },
+                "ranking_warning": {
+                    "type": "string",
+                    "description": "Set when the review has no parseable FINAL RANKING section"
+                },
+                "summary": { "type": "string" }
+            },
+            "required": ["success", "file_saved", "summary"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Save review failed"
+    }
+
+    async fn call(&self, arguments: Value, _ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_save_review(arguments).await
     }
-    Ok(council)
 }
 
 pub async fn handle_save_review(params: Value) -> Result<Value> {
-    let title = params["title"]
-        .as_str()
-        .context("Missing required parameter: title")?;
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
     // Try to get model from various sources in priority order:
     // 1. Explicit model parameter
     // 2. engine parameter (for backward compatibility)
@@ -30,27 +102,10 @@ pub async fn handle_save_review(params: Value) -> Result<Value> {
     let model = if model_trimmed.is_empty() { "claude" } else { model_trimmed };
 
     // Debug logging
-    eprintln!("DEBUG: save_review called with params: title={}, model={}, model_raw={:?}, engine={:?}",
+    log_debug!("save_review called with params: title={}, model={}, model_raw={:?}, engine={:?}",
         title, model, params.get("model"), params.get("engine"));
 
-    let model_for_file: String = {
-        let sanitized: String = model
-            .chars()
-            .map(|c: char| {
-                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
-                    c
-                } else {
-                    '-'
-                }
-            })
-            .collect();
-        let cleaned = sanitized.trim_matches('-');
-        if cleaned.is_empty() {
-            "claude".to_string()
-        } else {
-            sanitized
-        }
-    };
+    let model_for_file = sanitize_model(model);
 
     let review_content = params["content"]
         .as_str()
@@ -66,6 +121,9 @@ pub async fn handle_save_review(params: Value) -> Result<Value> {
         ));
     }
 
+    let _lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+
     // Build markdown content
     let markdown = format!(
         "# Peer Review\n- title: {}\n- model: {}\n\n## Review Content\n\n{}",
@@ -79,9 +137,32 @@ pub async fn handle_save_review(params: Value) -> Result<Value> {
             review_md_path.display(),
             env::current_dir().unwrap_or_else(|_| PathBuf::from(".")).display()))?;
 
-    Ok(json!({
+    // Extract the FINAL RANKING section and map labels back to model names
+    // through the mapping peer_review saved for this reviewer
+    let mut result = json!({
         "success": true,
         "file_saved": review_md_path.to_string_lossy(),
         "summary": format!("Peer review saved to {}", review_md_path.display())
-    }))
+    });
+
+    let ranking_path = match ranking::build_review_ranking(title, model, &review_md_path, review_content) {
+        Some(review_ranking) => {
+            let ranking_path = ranking::save_ranking(&review_md_path, &review_ranking)?;
+            result["ranking_file"] = json!(ranking_path.to_string_lossy());
+            result["ranking"] = serde_json::to_value(&review_ranking)?;
+            Some(ranking_path)
+        }
+        None => {
+            // Drop a ranking left over from an earlier save of this review
+            let _ = fs::remove_file(ranking::ranking_path_for(&review_md_path));
+            result["ranking_warning"] =
+                json!("No FINAL RANKING section found; structured ranking was not saved");
+            None
+        }
+    };
+
+    manifest.record_review(model, &review_md_path, ranking_path.as_deref())?;
+    manifest.save(&base_dir)?;
+
+    Ok(result)
 }
\ No newline at end of file
@@ -1,23 +1,77 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::slug::validate_slug;
+use crate::tools::{CouncilTool, ToolContext};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
-fn find_council_dir() -> Result<PathBuf> {
-    let home = env::var("HOME").context("HOME not set")?;
-    let council = PathBuf::from(home).join(".council");
-    if !council.exists() {
-        fs::create_dir_all(&council)
-            .context(format!("Failed to create council directory: {}", council.display()))?;
+pub struct SaveSummaryTool;
+
+#[async_trait]
+impl CouncilTool for SaveSummaryTool {
+    fn name(&self) -> &'static str {
+        "council.save_summary"
+    }
+
+    fn description(&self) -> &'static str {
+        "Save summary content to markdown file for use in Stage2/Stage3"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "Model name that generated the summary"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Summary content to save"
+                }
+            },
+            "required": ["title", "content"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "file_saved": {
+                    "type": "string",
+                    "description": "Path of the saved summary"
+                },
+                "summary": { "type": "string" }
+            },
+            "required": ["success", "file_saved", "summary"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Save summary failed"
+    }
+
+    async fn call(&self, arguments: Value, _ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_save_summary(arguments).await
     }
-    Ok(council)
 }
 
 pub async fn handle_save_summary(params: Value) -> Result<Value> {
-    let title = params["title"]
-        .as_str()
-        .context("Missing required parameter: title")?;
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
     let model_raw = params["model"]
         .as_str()
         .unwrap_or("unknown-model")
@@ -33,7 +87,7 @@ pub async fn handle_save_summary(params: Value) -> Result<Value> {
         .context("Missing required parameter: content")?;
 
     // Debug logging
-    eprintln!("DEBUG: save_summary called with params: title={}, model={}, content_len={}",
+    log_debug!("save_summary called with params: title={}, model={}, content_len={}",
         title, model, summary_content.len());
 
     let council_base = find_council_dir()?;
@@ -46,6 +100,9 @@ pub async fn handle_save_summary(params: Value) -> Result<Value> {
         ));
     }
 
+    let _lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+
     // Build markdown content
     let markdown = format!(
         "# Summary\n- title: {}\n- model: {}\n\n## Summary Content\n\n{}",
@@ -59,6 +116,9 @@ pub async fn handle_save_summary(params: Value) -> Result<Value> {
             summary_md_path.display(),
             env::current_dir().unwrap_or_else(|_| PathBuf::from(".")).display()))?;
 
+    manifest.record_summary(Some(model), &summary_md_path)?;
+    manifest.save(&base_dir)?;
+
     Ok(json!({
         "success": true,
         "file_saved": summary_md_path.to_string_lossy(),
@@ -0,0 +1,254 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::{Manifest, StageStatus};
+use crate::progress::Progress;
+use crate::slug::validate_slug;
+use crate::tools::finalize::{list_review_files, load_stage2_reviews};
+use crate::tools::peer_review::{list_answer_files, same_model};
+use crate::tools::{CouncilTool, ToolContext};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::path::Path;
+
+pub struct StatusTool;
+
+#[async_trait]
+impl CouncilTool for StatusTool {
+    fn name(&self) -> &'static str {
+        "council.status"
+    }
+
+    fn description(&self) -> &'static str {
+        "Report which models have answered and reviewed, which reviews are missing or malformed, and whether the session is ready for finalize"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                }
+            },
+            "required": ["title"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "title": { "type": "string" },
+                "query": { "type": ["string", "null"] },
+                "stages": { "type": "object" },
+                "answered": { "type": "array", "items": { "type": "object" } },
+                "reviews": { "type": "array", "items": { "type": "object" } },
+                "missing_reviews": { "type": "array", "items": { "type": "string" } },
+                "malformed_reviews": { "type": "array", "items": { "type": "object" } },
+                "extra_reviewers": { "type": "array", "items": { "type": "string" } },
+                "untracked_answers": { "type": "array", "items": { "type": "string" } },
+                "untracked_reviews": { "type": "array", "items": { "type": "string" } },
+                "finals": { "type": "array", "items": { "type": "object" } },
+                "ready_for_finalize": { "type": "boolean" },
+                "summary": { "type": "string" },
+                "next_steps": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["success", "title", "stages", "reviews", "ready_for_finalize", "summary", "next_steps"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Status failed"
+    }
+
+    async fn call(&self, arguments: Value, ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_status(arguments, ctx.progress).await
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Classify a loaded Stage2 review by the quality of its FINAL RANKING.
+fn ranking_problem(review: &Value, expected: usize) -> Option<String> {
+    let Some(ranking) = review.get("ranking") else {
+        return Some("no parseable FINAL RANKING section".to_string());
+    };
+    let entries = ranking["ranking"].as_array().map(|a| a.as_slice()).unwrap_or_default();
+    let unresolved = entries.iter().filter(|e| e["model"].is_null()).count();
+    if unresolved > 0 {
+        return Some(format!("{} ranked label(s) could not be resolved to a model", unresolved));
+    }
+    if expected > 0 && entries.len() < expected {
+        return Some(format!("ranks {} of {} responses", entries.len(), expected));
+    }
+    None
+}
+
+pub async fn handle_status(params: Value, progress: &Progress) -> Result<Value> {
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
+
+    // Debug logging
+    log_debug!("status called with params: title={}", title);
+
+    let council_base = find_council_dir()?;
+    let base_dir = council_base.join(title);
+
+    if !base_dir.exists() {
+        return Err(anyhow::anyhow!(
+            "Directory not found: {} (council base: {})",
+            base_dir.display(),
+            council_base.display()
+        ));
+    }
+
+    // Status only reports, so nothing it finds is written back
+    let mut manifest = Manifest::load_or_scan(&base_dir, title)?;
+
+    // Files on disk that no tool has recorded (e.g. written by hand)
+    let untracked_answers: Vec<String> = list_answer_files(&base_dir)?
+        .iter()
+        .map(|p| file_name(p))
+        .filter(|name| !manifest.answers.iter().any(|a| &a.file == name))
+        .collect();
+    let untracked_reviews: Vec<String> = list_review_files(&base_dir)?
+        .iter()
+        .map(|p| file_name(p))
+        .filter(|name| !manifest.reviews.iter().any(|r| &r.file == name && r.is_saved()))
+        .collect();
+
+    // Adopted in memory the way the stage tools will adopt them, so the
+    // counts below match what finalize would see
+    manifest.adopt_untracked_answers(&base_dir)?;
+    manifest.adopt_untracked_reviews(&base_dir)?;
+
+    let stage2_results = load_stage2_reviews(&manifest.review_paths(&base_dir), title, progress)?;
+
+    // Each reviewer ranks every answer except its own
+    let participants = &manifest.participants;
+    let mut reviews = Vec::new();
+    let mut missing_reviews = Vec::new();
+    let mut malformed_reviews = Vec::new();
+    for participant in participants {
+        let expected = manifest
+            .answers
+            .iter()
+            .filter(|a| !same_model(&a.model, participant))
+            .count();
+        let record = manifest
+            .reviews
+            .iter()
+            .find(|r| same_model(&r.reviewer, participant));
+
+        let (state, problem) = match record {
+            Some(r) if r.is_saved() => {
+                let loaded = stage2_results
+                    .iter()
+                    .find(|s| s["model"].as_str().is_some_and(|m| same_model(m, participant)));
+                let problem = match loaded {
+                    Some(review) => ranking_problem(review, expected),
+                    None => Some("review file could not be loaded".to_string()),
+                };
+                ("saved", problem)
+            }
+            Some(_) => ("requested", None),
+            None => ("missing", None),
+        };
+
+        if state != "saved" {
+            missing_reviews.push(participant.clone());
+        }
+        if let Some(problem) = &problem {
+            malformed_reviews.push(json!({
+                "reviewer": participant,
+                "file": record.map(|r| r.file.clone()),
+                "problem": problem
+            }));
+        }
+        reviews.push(json!({
+            "reviewer": participant,
+            "status": state,
+            "file": record.map(|r| r.file.clone()),
+            "ranking_ok": state == "saved" && problem.is_none()
+        }));
+    }
+
+    // Reviews from models that never answered still count as ballots
+    let extra_reviewers: Vec<String> = manifest
+        .reviews
+        .iter()
+        .filter(|r| r.is_saved() && !participants.iter().any(|p| same_model(p, &r.reviewer)))
+        .map(|r| r.reviewer.clone())
+        .collect();
+
+    let saved_reviews = manifest.reviews.iter().filter(|r| r.is_saved()).count();
+    // Reviews requested before an untracked answer appeared never saw it
+    let ready_for_finalize = !manifest.answers.is_empty()
+        && untracked_answers.is_empty()
+        && missing_reviews.is_empty()
+        && malformed_reviews.is_empty();
+
+    let mut next_steps = Vec::new();
+    if manifest.answers.is_empty() {
+        next_steps.push("Run first_answer from each model".to_string());
+    }
+    if !untracked_answers.is_empty() {
+        // peer_review records them before labeling; tally can bail first
+        next_steps.push(format!(
+            "Record the untracked answer file(s) {} by running peer_review {} by <model> (or remove them); reviewers who already saved a review must re-run it to see them",
+            untracked_answers.join(", "),
+            title
+        ));
+    }
+    for model in &missing_reviews {
+        next_steps.push(format!("Run peer_review {} by {} and save the review", title, model));
+    }
+    for malformed in &malformed_reviews {
+        next_steps.push(format!(
+            "Fix the review by {}: {}",
+            malformed["reviewer"].as_str().unwrap_or_default(),
+            malformed["problem"].as_str().unwrap_or_default()
+        ));
+    }
+    if ready_for_finalize && manifest.stages.stage3.status != StageStatus::Complete {
+        next_steps.push(format!("Run finalize {} by <model>", title));
+    }
+
+    Ok(json!({
+        "success": true,
+        "title": title,
+        "query": manifest.query,
+        "stages": manifest.stages,
+        "answered": manifest.answers.iter().map(|a| json!({
+            "model": a.model,
+            "file": a.file,
+            "saved_at": a.saved_at
+        })).collect::<Vec<_>>(),
+        "reviews": reviews,
+        "missing_reviews": missing_reviews,
+        "malformed_reviews": malformed_reviews,
+        "extra_reviewers": extra_reviewers,
+        "untracked_answers": untracked_answers,
+        "untracked_reviews": untracked_reviews,
+        "finals": manifest.finals,
+        "ready_for_finalize": ready_for_finalize,
+        "summary": format!(
+            "{} answer(s), {} saved review(s), {} missing, {} malformed; {}",
+            manifest.answers.len(),
+            saved_reviews,
+            missing_reviews.len(),
+            malformed_reviews.len(),
+            if ready_for_finalize { "ready for finalize" } else { "not ready for finalize" }
+        ),
+        "next_steps": next_steps
+    }))
+}
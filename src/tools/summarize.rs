@@ -1,24 +1,87 @@
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::slug::validate_slug;
+use crate::tools::{CouncilTool, ToolContext};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use chrono::Utc;
 use serde_json::{json, Value};
-use std::env;
 use std::fs;
-use std::path::PathBuf;
-
-fn find_council_dir() -> Result<PathBuf> {
-    let home = env::var("HOME").context("HOME not set")?;
-    let council = PathBuf::from(home).join(".council");
-    if !council.exists() {
-        fs::create_dir_all(&council)
-            .context(format!("Failed to create council directory: {}", council.display()))?;
+
+pub struct SummarizeTool;
+
+#[async_trait]
+impl CouncilTool for SummarizeTool {
+    fn name(&self) -> &'static str {
+        "council.summarize"
+    }
+
+    fn description(&self) -> &'static str {
+        "Generate a summary prompt for large documents to reduce token costs in Stage2/Stage3"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name (slug)"
+                },
+                "model": {
+                    "type": "string",
+                    "description": "Model name performing the summary (e.g., sonnet, gemini, gpt-5.1)",
+                    "default": "unknown-model"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Original content to summarize"
+                },
+                "max_length": {
+                    "type": "integer",
+                    "description": "Target summary length in characters (default: 2000)",
+                    "minimum": 1,
+                    "default": 2000
+                }
+            },
+            "required": ["title", "content"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "action": { "type": "string" },
+                "summary_prompt": { "type": "string" },
+                "output_file": { "type": "string" },
+                "output_dir": { "type": "string" },
+                "prompt_file": {
+                    "type": "string",
+                    "description": "Path where the summary prompt was written"
+                },
+                "instruction": { "type": "string" }
+            },
+            "required": ["success", "action", "summary_prompt", "output_file", "output_dir", "prompt_file"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Summarize failed"
+    }
+
+    async fn call(&self, arguments: Value, _ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_summarize(arguments).await
     }
-    Ok(council)
 }
 
 pub async fn handle_summarize(params: Value) -> Result<Value> {
-    let title = params["title"]
-        .as_str()
-        .context("Missing required parameter: title")?;
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
     let model_raw = params["model"]
         .as_str()
         .unwrap_or("unknown-model")
@@ -36,8 +99,8 @@ pub async fn handle_summarize(params: Value) -> Result<Value> {
         .unwrap_or(2000); // Default: 2000 characters
 
     // Debug logging
-    eprintln!(
-        "DEBUG: summarize called with params: title={}, model={}, content_len={}, max_length={}",
+    log_debug!(
+        "summarize called with params: title={}, model={}, content_len={}, max_length={}",
         title,
         model,
         content.len(),
@@ -53,6 +116,10 @@ pub async fn handle_summarize(params: Value) -> Result<Value> {
         base_dir.display()
     ))?;
 
+    // Make sure the session has a manifest even if summarize runs first
+    let _lock = Manifest::lock(&base_dir).await?;
+    Manifest::load_or_rebuild(&base_dir, title)?;
+
     // Build summary prompt
     let summary_prompt = format!(
         r#"Please summarize the following content concisely. The summary should be comprehensive but concise, capturing all key points and important details. Target length: approximately {} characters.
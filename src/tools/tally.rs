@@ -0,0 +1,153 @@
+use crate::aggregate;
+use crate::council_dir::find_council_dir;
+use crate::manifest::Manifest;
+use crate::progress::Progress;
+use crate::slug::validate_slug;
+use crate::tools::finalize::{load_stage1_answers, load_stage2_reviews};
+use crate::tools::{CouncilTool, ToolContext};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::fs;
+
+pub struct TallyTool;
+
+#[async_trait]
+impl CouncilTool for TallyTool {
+    fn name(&self) -> &'static str {
+        "council.tally"
+    }
+
+    fn description(&self) -> &'static str {
+        "Aggregate Stage2 peer rankings into a leaderboard (Borda, mean rank, Copeland/Condorcet, Schulze) and save tally.json"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Conversation title/directory name"
+                }
+            },
+            "required": ["title"]
+        })
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "file_saved": {
+                    "type": "string",
+                    "description": "Path of tally.json"
+                },
+                "leaderboard": {
+                    "type": "array",
+                    "items": { "type": "object" },
+                    "description": "Models in aggregate order with their scores"
+                },
+                "condorcet_winner": { "type": ["string", "null"] },
+                "ballots": {
+                    "type": "integer",
+                    "description": "Number of rankings that were counted"
+                },
+                "skipped_reviews": { "type": "array", "items": { "type": "string" } },
+                "summary": { "type": "string" }
+            },
+            "required": ["success", "file_saved", "leaderboard", "ballots", "summary"]
+        })
+    }
+
+    fn failure_message(&self) -> &'static str {
+        "Tally failed"
+    }
+
+    async fn call(&self, arguments: Value, ctx: &ToolContext<'_>) -> Result<Value> {
+        handle_tally(arguments, ctx.progress).await
+    }
+}
+
+pub async fn handle_tally(params: Value, progress: &Progress) -> Result<Value> {
+    let title = validate_slug(
+        params["title"]
+            .as_str()
+            .context("Missing required parameter: title")?,
+    )?;
+
+    // Debug logging
+    log_debug!("tally called with params: title={}", title);
+
+    let council_base = find_council_dir()?;
+    let base_dir = council_base.join(title);
+
+    if !base_dir.exists() {
+        return Err(anyhow::anyhow!(
+            "Directory not found: {} (council base: {})",
+            base_dir.display(),
+            council_base.display()
+        ));
+    }
+
+    let _lock = Manifest::lock(&base_dir).await?;
+    let mut manifest = Manifest::load_or_rebuild(&base_dir, title)?;
+    manifest.adopt_untracked_answers(&base_dir)?;
+    manifest.adopt_untracked_reviews(&base_dir)?;
+
+    // Steps: answers, one per review, aggregation
+    let review_paths = manifest.review_paths(&base_dir);
+    progress.set_total(2 + review_paths.len() as u64);
+
+    let candidates: Vec<String> = load_stage1_answers(&manifest.answer_paths(&base_dir))?
+        .iter()
+        .filter_map(|r| r.get("model").and_then(|v| v.as_str()).map(|m| m.to_string()))
+        .collect();
+    progress.step(format!("{} Stage1 answer(s) loaded", candidates.len()));
+    let stage2_results = load_stage2_reviews(&review_paths, title, progress)?;
+    let ballots = aggregate::ballots_from_reviews(&stage2_results);
+
+    if ballots.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No parseable peer rankings found in {}. Run peer_review and save_review first.",
+            base_dir.display()
+        ));
+    }
+
+    // Reviews whose FINAL RANKING could not be parsed or resolved
+    let skipped: Vec<String> = stage2_results
+        .iter()
+        .filter_map(|r| r["model"].as_str())
+        .filter(|reviewer| !ballots.iter().any(|b| b.reviewer == *reviewer))
+        .map(|reviewer| reviewer.to_string())
+        .collect();
+
+    let tally = aggregate::tally(&candidates, &ballots);
+    progress.step(format!("Aggregated {} ballot(s)", ballots.len()));
+
+    let tally_path = base_dir.join("tally.json");
+    let tally_json = json!({
+        "title": title,
+        "created_at": Utc::now().to_rfc3339(),
+        "ballots": ballots,
+        "skipped_reviews": skipped,
+        "tally": tally
+    });
+    fs::write(&tally_path, serde_json::to_string_pretty(&tally_json)?)
+        .context(format!("Failed to write tally file: {}", tally_path.display()))?;
+
+    manifest.record_tally(&tally_path)?;
+    manifest.save(&base_dir)?;
+
+    Ok(json!({
+        "success": true,
+        "file_saved": tally_path.to_string_lossy(),
+        "leaderboard": tally.leaderboard,
+        "condorcet_winner": tally.condorcet_winner,
+        "ballots": ballots.len(),
+        "skipped_reviews": skipped,
+        "summary": tally.summary()
+    }))
+}